@@ -0,0 +1,30 @@
+use std::{env, process::Command};
+
+// record build information that is shown by the version command
+fn main() {
+    let git_commit = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|git_commit| git_commit.trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=BOUNCY_WORLD_GIT_COMMIT={}", git_commit);
+
+    let mut features: Vec<String> = env::vars()
+        .filter_map(|(variable, _)| {
+            variable
+                .strip_prefix("CARGO_FEATURE_")
+                .map(|feature| feature.to_lowercase().replace('_', "-"))
+        })
+        .collect();
+    features.sort();
+    println!(
+        "cargo:rustc-env=BOUNCY_WORLD_FEATURES={}",
+        features.join(",")
+    );
+
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs/heads");
+}
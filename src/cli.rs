@@ -1,182 +1,662 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+use clap::{
+    Arg, ArgMatches, Args, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum, ValueHint,
+};
 
 use crate::{
-    config::Config,
+    completions,
+    config::{Config, ConfigLayers},
     constants::{
-        CONFIG_COMMAND_DESCRIPTION, CONFIG_COMMAND_LONG, CONFIG_COMMAND_SHORT,
-        HELP_COMMAND_DESCRIPTION, HELP_COMMAND_LONG, HELP_COMMAND_SHORT, JSON_CONFIG_PATH,
-        NEW_COMMAND_DESCRIPTION, NEW_COMMAND_LONG, NEW_COMMAND_SHORT, SAVE_COMMAND_DESCRIPTION,
-        SAVE_COMMAND_LONG, SAVE_COMMAND_SHORT, WORLD_SAVE_FILE_EXTENSION, YAML_CONFIG_PATH,
-        YML_CONFIG_PATH,
+        COMPLETIONS_COMMAND_DESCRIPTION, COMPLETIONS_COMMAND_LONG, CONFIG_COMMAND_DESCRIPTION,
+        CONFIG_COMMAND_LONG, CONFIG_COMMAND_SHORT, CONFIG_SHOW_COMMAND,
+        CONFIG_SHOW_COMMAND_DESCRIPTION, DIFF_COMMAND_DESCRIPTION, DIFF_COMMAND_LONG,
+        DIFF_COMMAND_SHORT, ENVIRONMENT_VARIABLE_PREFIX, INSPECT_COMMAND_DESCRIPTION,
+        INSPECT_COMMAND_LONG, INSPECT_COMMAND_SHORT, JSON_CONFIG_PATH, LIST_COMMAND_DESCRIPTION,
+        LIST_COMMAND_LONG, LIST_COMMAND_SHORT, MERGE_COMMAND_DESCRIPTION, MERGE_COMMAND_LONG,
+        MERGE_COMMAND_SHORT, NEW_COMMAND_DESCRIPTION, NEW_COMMAND_LONG, NEW_COMMAND_SHORT,
+        PREDICT_COMMAND_DESCRIPTION, PREDICT_COMMAND_LONG, PREDICT_COMMAND_SHORT,
+        RUN_COMMAND_DESCRIPTION, RUN_COMMAND_LONG, RUN_COMMAND_SHORT, SAVE_COMMAND_DESCRIPTION,
+        SAVE_COMMAND_LONG, SAVE_COMMAND_SHORT, TOML_CONFIG_PATH, VERSION_COMMAND_DESCRIPTION,
+        VERSION_COMMAND_LONG, VERSION_COMMAND_SHORT, WORLD_SAVE_FILE_EXTENSION,
+        WORLD_SAVE_LOCATION, YAML_CONFIG_PATH,
     },
+    diff::SaveDiff,
+    inspect::{self, SaveInspection, SaveSummary},
+    predict::SavePrediction,
     save::Save,
-    world::World,
+    version::VersionInfo,
+    world::{MergeOptions, SizeMismatch, World},
 };
 
-#[derive(Eq, PartialEq, Debug)]
-pub enum BouncyWorldWindow {
-    Show,
-    Hide,
+const CONFIG_OVERRIDES_HEADING: &str = "Config overrides";
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "bouncy-world",
+    about = format!("The Bouncy World Engine - v{}", env!("CARGO_PKG_VERSION")),
+    args_conflicts_with_subcommands = true
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// world save or config file to run (same as the run command)
+    #[arg(value_hint = ValueHint::FilePath)]
+    pub path: Option<PathBuf>,
 }
 
-// TODO: add version command
-// TODO: add update command
-pub fn run_command(given_command: &str) -> (Config, Save, BouncyWorldWindow) {
-    // start with default config & an empty world save
-    let mut running_config: Config = Config::new();
-    let running_world_save: Save;
-
-    if Path::new(&given_command).exists() {
-        // the argument is a valid path, try loading it as a world save or config
-        (running_config, running_world_save) = try_load_from_files(&given_command);
-        return (running_config, running_world_save, BouncyWorldWindow::Show);
-    } else if given_command == NEW_COMMAND_LONG || given_command == NEW_COMMAND_SHORT {
-        // run new command:
-        running_config = Config::new();
-        running_world_save = Save::new(World::new(&running_config));
-        let save_file_location = running_world_save.save(&running_config);
-        println!(
-            "saved default config and default world save to known universe: {}",
-            save_file_location
-        );
-    } else if given_command == SAVE_COMMAND_LONG || given_command == SAVE_COMMAND_SHORT {
-        // run save command:
-        running_config = if Path::new(YAML_CONFIG_PATH).exists() {
-            Config::load_from_yaml_file(YAML_CONFIG_PATH)
-        } else if Path::new(YML_CONFIG_PATH).exists() {
-            Config::load_from_yaml_file(YML_CONFIG_PATH)
-        } else if Path::new(JSON_CONFIG_PATH).exists() {
-            Config::load_from_json_file(JSON_CONFIG_PATH)
-        } else {
-            println!("no config found, loaded default config");
-            running_config
-        };
-
-        running_world_save = Save::new(World::new(&running_config));
-        let save_file_location = running_world_save.save(&running_config);
-        println!(
-            "saved config and world save to known universe: {}",
-            save_file_location
-        );
-    } else if given_command == CONFIG_COMMAND_LONG || given_command == CONFIG_COMMAND_SHORT {
-        // run config command:
-        if !Path::new(JSON_CONFIG_PATH).exists() {
-            running_config = Config::new();
-            // TODO: add ability to save to yaml file
-            // running_config.save_to_yaml_file(JSON_CONFIG_PATH);
-            running_config.save_to_json_file(JSON_CONFIG_PATH);
-            println!("save config file in current directory");
-        } else {
-            println!("config file already exists in current directory");
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    #[command(
+        name = RUN_COMMAND_LONG,
+        visible_alias = RUN_COMMAND_SHORT,
+        about = RUN_COMMAND_DESCRIPTION,
+    )]
+    Run {
+        /// world save or config file to run
+        #[arg(value_hint = ValueHint::FilePath)]
+        path: Option<PathBuf>,
+        /// write bounce and corner hit statistics as json to this file when the window is closed
+        #[arg(long, value_hint = ValueHint::AnyPath)]
+        stats: Option<PathBuf>,
+    },
+    #[command(
+        name = NEW_COMMAND_LONG,
+        visible_alias = NEW_COMMAND_SHORT,
+        about = NEW_COMMAND_DESCRIPTION,
+    )]
+    New {
+        /// directory to save the new world in
+        #[arg(short, long, default_value = WORLD_SAVE_LOCATION, value_hint = ValueHint::DirPath)]
+        output: PathBuf,
+        #[command(flatten)]
+        metadata: SaveMetadataArgs,
+    },
+    #[command(
+        name = SAVE_COMMAND_LONG,
+        visible_alias = SAVE_COMMAND_SHORT,
+        about = SAVE_COMMAND_DESCRIPTION,
+    )]
+    Save {
+        /// config file to save (default: the config in the current directory)
+        #[arg(short, long, value_hint = ValueHint::FilePath)]
+        config: Option<PathBuf>,
+        /// directory to save the world in
+        #[arg(short, long, default_value = WORLD_SAVE_LOCATION, value_hint = ValueHint::DirPath)]
+        output: PathBuf,
+        #[command(flatten)]
+        metadata: SaveMetadataArgs,
+    },
+    #[command(
+        name = CONFIG_COMMAND_LONG,
+        visible_alias = CONFIG_COMMAND_SHORT,
+        about = CONFIG_COMMAND_DESCRIPTION,
+    )]
+    Config {
+        #[command(subcommand)]
+        command: Option<ConfigCommand>,
+        /// format of the generated config file
+        #[arg(short, long, value_enum, default_value_t = ConfigFormat::Json)]
+        format: ConfigFormat,
+    },
+    #[command(
+        name = VERSION_COMMAND_LONG,
+        visible_alias = VERSION_COMMAND_SHORT,
+        about = VERSION_COMMAND_DESCRIPTION,
+    )]
+    Version {
+        /// print the version info as json, for tooling
+        #[arg(long)]
+        json: bool,
+    },
+    #[command(
+        name = LIST_COMMAND_LONG,
+        visible_alias = LIST_COMMAND_SHORT,
+        about = LIST_COMMAND_DESCRIPTION,
+    )]
+    List {
+        /// directory to list the world saves of
+        #[arg(short, long, default_value = WORLD_SAVE_LOCATION, value_hint = ValueHint::DirPath)]
+        dir: PathBuf,
+        /// print the world saves as json
+        #[arg(long)]
+        json: bool,
+    },
+    #[command(
+        name = INSPECT_COMMAND_LONG,
+        visible_alias = INSPECT_COMMAND_SHORT,
+        about = INSPECT_COMMAND_DESCRIPTION,
+    )]
+    Inspect {
+        /// world save file, or the directory it is in
+        #[arg(value_hint = ValueHint::FilePath)]
+        save: PathBuf,
+        /// print the summary as json
+        #[arg(long)]
+        json: bool,
+    },
+    #[command(
+        name = DIFF_COMMAND_LONG,
+        visible_alias = DIFF_COMMAND_SHORT,
+        about = DIFF_COMMAND_DESCRIPTION,
+    )]
+    Diff {
+        /// world save file (or its directory) to compare from
+        #[arg(value_hint = ValueHint::FilePath)]
+        old_save: PathBuf,
+        /// world save file (or its directory) to compare to
+        #[arg(value_hint = ValueHint::FilePath)]
+        new_save: PathBuf,
+        /// print the differences as json
+        #[arg(long)]
+        json: bool,
+    },
+    #[command(
+        name = MERGE_COMMAND_LONG,
+        visible_alias = MERGE_COMMAND_SHORT,
+        about = MERGE_COMMAND_DESCRIPTION,
+    )]
+    Merge {
+        /// world save files (or their directories) to merge
+        #[arg(required = true, num_args = 2.., value_hint = ValueHint::FilePath)]
+        saves: Vec<PathBuf>,
+        /// index of the world save to take the world size, type and background from
+        #[arg(short, long, default_value_t = 0)]
+        background: usize,
+        /// added to the entity positions of every other world save, as x,y
+        #[arg(long, default_value = "0,0", value_parser = parse_offset, allow_hyphen_values = true)]
+        offset: (i32, i32),
+        /// how entities are fitted into the merged world when the world sizes differ
+        #[arg(short, long, value_enum, default_value_t = MergeSizeMismatch::Keep)]
+        size_mismatch: MergeSizeMismatch,
+        /// directory to save the merged world in
+        #[arg(short, long, default_value = WORLD_SAVE_LOCATION, value_hint = ValueHint::DirPath)]
+        output: PathBuf,
+        #[command(flatten)]
+        metadata: SaveMetadataArgs,
+    },
+    #[command(
+        name = PREDICT_COMMAND_LONG,
+        visible_alias = PREDICT_COMMAND_SHORT,
+        about = PREDICT_COMMAND_DESCRIPTION,
+    )]
+    Predict {
+        /// world save file, or the directory it is in
+        #[arg(value_hint = ValueHint::FilePath)]
+        save: PathBuf,
+        /// check the predictions by stepping the world frame by frame
+        #[arg(long)]
+        verify: bool,
+        /// print the predictions as json
+        #[arg(long)]
+        json: bool,
+    },
+    #[command(
+        name = COMPLETIONS_COMMAND_LONG,
+        about = COMPLETIONS_COMMAND_DESCRIPTION,
+    )]
+    Completions {
+        /// shell to generate the completion script for
+        #[arg(value_enum)]
+        shell: CompletionShell,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommand {
+    #[command(name = CONFIG_SHOW_COMMAND, about = CONFIG_SHOW_COMMAND_DESCRIPTION)]
+    Show {
+        /// show the effective config, with overrides and where each value came from
+        #[arg(long)]
+        resolved: bool,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum CompletionShell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+// metadata given to world saves created by a command
+#[derive(Args, Debug)]
+#[command(next_help_heading = "Save metadata")]
+pub struct SaveMetadataArgs {
+    /// title of the world save
+    #[arg(long)]
+    pub title: Option<String>,
+    /// author of the world save
+    #[arg(long)]
+    pub author: Option<String>,
+    /// description of the world save
+    #[arg(long)]
+    pub description: Option<String>,
+    /// tag of the world save, can be given more than once
+    #[arg(long = "tag", value_name = "TAG")]
+    pub tags: Vec<String>,
+}
+
+impl Cli {
+    // the file to write the world stats to when the window is closed, only given to the run command
+    pub fn stats_output(&self) -> Option<PathBuf> {
+        match &self.command {
+            Some(Command::Run { stats, .. }) => stats.clone(),
+            _ => None,
         }
-        std::process::exit(0);
-    } else if given_command == HELP_COMMAND_LONG || given_command == HELP_COMMAND_SHORT {
-        // run help command:
-        print_help_message();
-        std::process::exit(0);
-    } else {
-        println!("unknown command: {:?}", given_command);
-        std::process::exit(0);
     }
+}
 
-    (running_config, running_world_save, BouncyWorldWindow::Hide)
+impl SaveMetadataArgs {
+    fn apply_to(self, world_save: &mut Save) {
+        world_save.title = self.title;
+        world_save.author = self.author;
+        world_save.description = self.description;
+        world_save.tags = self.tags;
+    }
 }
 
-fn try_load_from_files(given_path: &str) -> (Config, Save) {
-    // start with an empty config & an empty world save
-    let running_config: Config;
-    let running_world_save: Save;
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum MergeSizeMismatch {
+    /// keep entity positions as they are
+    Keep,
+    /// scale entity positions by the ratio between the world sizes
+    Scale,
+    /// move entities that are outside the merged world back inside it
+    Clamp,
+    /// grow the merged world to fit the largest world
+    Grow,
+}
 
-    let path = Path::new(&given_path);
-    let path_file_extension = path
-        .extension()
-        .expect("expected config file or bouncy-world file");
-    if path_file_extension == WORLD_SAVE_FILE_EXTENSION {
-        (running_config, running_world_save) = try_load_from_bouncy_world_save(path);
-    } else if path.ends_with(YAML_CONFIG_PATH) || path.ends_with(YML_CONFIG_PATH) {
-        (running_config, running_world_save) = load_config_from_yaml(given_path);
-    } else if path.ends_with(JSON_CONFIG_PATH) {
-        (running_config, running_world_save) = load_config_from_json(given_path);
-    } else {
-        println!("unknown command: {:?}", given_path);
-        std::process::exit(0);
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum ConfigFormat {
+    Json,
+    Yaml,
+    Toml,
+}
+
+// the cli definition, with a global --field-name flag for every config field
+pub fn command() -> clap::Command {
+    Config::field_names()
+        .into_iter()
+        .fold(Cli::command(), |command, field_name| {
+            let help = format!(
+                "overrides {} (env: {}{})",
+                field_name,
+                ENVIRONMENT_VARIABLE_PREFIX,
+                field_name.to_uppercase()
+            );
+            command.arg(
+                Arg::new(field_name.clone())
+                    .long(field_name.replace('_', "-"))
+                    .value_name("VALUE")
+                    .global(true)
+                    .help_heading(CONFIG_OVERRIDES_HEADING)
+                    .help(help),
+            )
+        })
+}
+
+// parse the command line arguments into the cli and the config flags,
+// the config flags are (field_name, raw_value) pairs, e.g. ("world_width", "800")
+pub fn parse_args() -> (Cli, Vec<(String, String)>) {
+    let matches = command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|error| error.exit());
+
+    // global args are propagated down, so the deepest sub command has all of them
+    let mut deepest_matches: &ArgMatches = &matches;
+    while let Some((_, sub_command_matches)) = deepest_matches.subcommand() {
+        deepest_matches = sub_command_matches;
     }
+    let config_flags = Config::field_names()
+        .into_iter()
+        .filter_map(|field_name| {
+            deepest_matches
+                .get_one::<String>(&field_name)
+                .map(|raw_value| (field_name.clone(), raw_value.to_string()))
+        })
+        .collect();
 
-    (running_config, running_world_save)
+    (cli, config_flags)
 }
 
-fn try_load_from_bouncy_world_save(world_save_path: &Path) -> (Config, Save) {
-    // start with default config & default world save
-    let mut running_config: Config = Config::new();
-    let mut running_world_save: Save = Save::new(World::new(&running_config));
+// layer the config file found in the given directory, the environment and the command line flags
+pub fn load_layered_config(
+    dir: &Path,
+    config_flags: &[(String, String)],
+) -> Result<ConfigLayers, String> {
+    let config_layers = match Config::find_config_file(dir) {
+        Some(config_path) => ConfigLayers::new().with_file(&config_path)?,
+        None => ConfigLayers::new(),
+    };
 
-    // try to load world, use default if fails
-    running_world_save = match Save::load(world_save_path) {
-        Some(world_save) => {
-            println!("loaded from world save");
-            world_save
+    config_layers.with_environment()?.with_flags(config_flags)
+}
+
+// run the given cli command, returns the config and world save to show in the bouncy-world window
+// TODO: add update command
+pub fn run_command(
+    cli: Cli,
+    config_flags: &[(String, String)],
+) -> Result<Option<(Config, Save)>, String> {
+    let command = match (cli.command, cli.path) {
+        (Some(command), _) => command,
+        (None, path) => Command::Run { path, stats: None },
+    };
+
+    match command {
+        Command::Run {
+            path: Some(path), ..
+        } => {
+            // the argument is a path, try loading it as a world save or config
+            try_load_from_files(&path, config_flags).map(Some)
         }
-        None => {
-            println!("unable to load save, loaded default world save");
-            running_world_save
+        Command::Run { path: None, .. } => {
+            // no path passed in, try to load config from current directory,
+            // no config found uses the default config
+            let running_config = load_layered_config(Path::new("."), config_flags)?.resolve()?;
+            let running_world_save = Save::new(new_world(&running_config)?);
+            Ok(Some((running_config, running_world_save)))
         }
-    };
+        Command::New { output, metadata } => {
+            let running_config = ConfigLayers::new()
+                .with_environment()?
+                .with_flags(config_flags)?
+                .resolve()?;
+            let mut running_world_save = Save::new(new_world(&running_config)?);
+            metadata.apply_to(&mut running_world_save);
+            let save_file_location = running_world_save.save(&running_config, &output)?;
+            println!(
+                "saved default config and default world save to known universe: {}",
+                save_file_location
+            );
+            Ok(None)
+        }
+        Command::Save {
+            config,
+            output,
+            metadata,
+        } => {
+            let config_layers = match config {
+                Some(config_path) if !Config::is_config_file(&config_path) => {
+                    return Err(format!("not a config file: {:?}", config_path));
+                }
+                Some(config_path) if !config_path.exists() => {
+                    return Err(format!("config file not found: {:?}", config_path));
+                }
+                Some(config_path) => ConfigLayers::new()
+                    .with_file(&config_path)?
+                    .with_environment()?
+                    .with_flags(config_flags)?,
+                None => {
+                    if Config::find_config_file(Path::new(".")).is_none() {
+                        println!("no config found, loaded default config");
+                    }
+                    load_layered_config(Path::new("."), config_flags)?
+                }
+            };
+
+            let running_config = config_layers.resolve()?;
+            let mut running_world_save = Save::new(new_world(&running_config)?);
+            metadata.apply_to(&mut running_world_save);
+            let save_file_location = running_world_save.save(&running_config, &output)?;
+            println!(
+                "saved config and world save to known universe: {}",
+                save_file_location
+            );
+            Ok(None)
+        }
+        Command::Config {
+            command: Some(ConfigCommand::Show { resolved }),
+            ..
+        } => {
+            if resolved {
+                load_layered_config(Path::new("."), config_flags)?.print_resolved();
+            } else {
+                let config_path = Config::find_config_file(Path::new("."))
+                    .ok_or("no config found in current directory")?;
+                println!("{}", config_path.display());
+                print!(
+                    "{}",
+                    serde_yaml::to_string(&Config::load_from_file(&config_path)?)
+                        .map_err(|error| format!("could not write config as yaml: {}", error))?
+                );
+            }
+            Ok(None)
+        }
+        Command::Config {
+            command: None,
+            format,
+        } => {
+            if let Some(config_path) = Config::find_config_file(Path::new(".")) {
+                println!(
+                    "config file already exists in current directory: {}",
+                    config_path.display()
+                );
+                return Ok(None);
+            }
+
+            let running_config = Config::new();
+            match format {
+                ConfigFormat::Json => running_config.save_to_json_file(JSON_CONFIG_PATH)?,
+                ConfigFormat::Yaml => running_config.save_to_yaml_file(YAML_CONFIG_PATH)?,
+                ConfigFormat::Toml => running_config.save_to_toml_file(TOML_CONFIG_PATH)?,
+            }
+            println!("save config file in current directory");
+            Ok(None)
+        }
+        Command::List { dir, json } => {
+            let mut save_summaries = vec![];
+            for save_path in Save::find_save_files(&dir) {
+                match Save::try_load(&save_path) {
+                    Ok(world_save) => {
+                        save_summaries.push(SaveSummary::new(&save_path, &world_save))
+                    }
+                    Err(error) => eprintln!("warning: {}", error),
+                }
+            }
+
+            if json {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&save_summaries)
+                        .expect("could not write world saves as json")
+                );
+            } else if save_summaries.is_empty() {
+                println!("no world saves found in {}", dir.display());
+            } else {
+                inspect::print_save_summaries(&save_summaries);
+            }
+            Ok(None)
+        }
+        Command::Inspect { save, json } => {
+            let save_path = inspect::find_save_file(&save)?;
+            let world_save = Save::try_load(&save_path)?;
+            let save_inspection = SaveInspection::new(&save_path, &world_save);
+            if json {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&save_inspection)
+                        .expect("could not write world save summary as json")
+                );
+            } else {
+                save_inspection.print();
+            }
+            Ok(None)
+        }
+        Command::Diff {
+            old_save,
+            new_save,
+            json,
+        } => {
+            let old_save = Save::try_load(&inspect::find_save_file(&old_save)?)?;
+            let new_save = Save::try_load(&inspect::find_save_file(&new_save)?)?;
+            let save_diff = SaveDiff::new(&old_save, &new_save);
+            if json {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&save_diff)
+                        .expect("could not write differences as json")
+                );
+            } else {
+                save_diff.print();
+            }
+            Ok(None)
+        }
+        Command::Merge {
+            saves,
+            background,
+            offset,
+            size_mismatch,
+            output,
+            metadata,
+        } => {
+            let mut worlds = vec![];
+            for save in &saves {
+                worlds.push(Save::try_load(&inspect::find_save_file(save)?)?.world);
+            }
+
+            let merge_options = MergeOptions {
+                background_from: background,
+                offset,
+                size_mismatch: match size_mismatch {
+                    MergeSizeMismatch::Keep => SizeMismatch::Keep,
+                    MergeSizeMismatch::Scale => SizeMismatch::Scale,
+                    MergeSizeMismatch::Clamp => SizeMismatch::Clamp,
+                    MergeSizeMismatch::Grow => SizeMismatch::Grow,
+                },
+            };
+            let mut merged_world_save = Save::new(World::merge(worlds, &merge_options)?);
+            metadata.apply_to(&mut merged_world_save);
+            let merged_config = Config::reverse_from_world_save(&merged_world_save);
+            let save_file_location = merged_world_save.save(&merged_config, &output)?;
+            println!(
+                "saved merged world save to known universe: {}",
+                save_file_location
+            );
+            Ok(None)
+        }
+        Command::Predict { save, verify, json } => {
+            let save_path = inspect::find_save_file(&save)?;
+            let world_save = Save::try_load(&save_path)?;
+            let mut save_prediction = SavePrediction::new(&save_path, &world_save);
+            if verify {
+                save_prediction.verify(world_save);
+            }
+            if json {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&save_prediction)
+                        .expect("could not write predictions as json")
+                );
+            } else {
+                save_prediction.print();
+            }
+
+            if save_prediction.is_verified() {
+                Ok(None)
+            } else {
+                Err("some predictions do not match stepping the world".to_string())
+            }
+        }
+        Command::Completions { shell } => {
+            completions::print_completions(shell);
+            Ok(None)
+        }
+        Command::Version { json } => {
+            if json {
+                VersionInfo::new().print_json();
+            } else {
+                VersionInfo::new().print();
+            }
+            Ok(None)
+        }
+    }
+}
+
+// parse an offset given as x,y, e.g. 10,-20
+fn parse_offset(offset: &str) -> Result<(i32, i32), String> {
+    let (x_offset, y_offset) = offset.split_once(',').ok_or("expected an offset as x,y")?;
+    let x_offset = x_offset
+        .trim()
+        .parse::<i32>()
+        .map_err(|error| error.to_string())?;
+    let y_offset = y_offset
+        .trim()
+        .parse::<i32>()
+        .map_err(|error| error.to_string())?;
+    Ok((x_offset, y_offset))
+}
+
+fn try_load_from_files(
+    path: &Path,
+    config_flags: &[(String, String)],
+) -> Result<(Config, Save), String> {
+    if !path.exists() {
+        return Err(format!("file not found: {:?}", path));
+    }
+
+    if path
+        .extension()
+        .is_some_and(|extension| extension == WORLD_SAVE_FILE_EXTENSION)
+    {
+        try_load_from_bouncy_world_save(path, config_flags)
+    } else if Config::is_config_file(path) {
+        load_config_from_file(path, config_flags)
+    } else {
+        Err(format!(
+            "expected config file or bouncy-world file: {:?}",
+            path
+        ))
+    }
+}
+
+fn try_load_from_bouncy_world_save(
+    world_save_path: &Path,
+    config_flags: &[(String, String)],
+) -> Result<(Config, Save), String> {
+    let running_world_save = Save::try_load(world_save_path)?;
+    println!("loaded from world save");
 
     // read from config in same directory as the world save
     let world_save_dir = world_save_path
         .parent()
         .expect("could not get directory from path");
-    let world_save_dir = world_save_dir
-        .to_str()
-        .expect("could not convert path to a str");
-    let world_yaml_config = &format!("{}\\{}", world_save_dir, YAML_CONFIG_PATH);
-    let world_yml_config = &format!("{}\\{}", world_save_dir, YML_CONFIG_PATH);
-    let world_json_config = &format!("{}\\{}", world_save_dir, JSON_CONFIG_PATH);
-
-    running_config = if Path::new(world_yaml_config).exists() {
-        Config::load_from_yaml_file(world_yaml_config)
-    } else if Path::new(world_yml_config).exists() {
-        Config::load_from_yaml_file(world_yml_config)
-    } else if Path::new(world_json_config).exists() {
-        Config::load_from_json_file(world_json_config)
-    }
-    // if no config was found, try to load config from current directory
-    else if Path::new(YAML_CONFIG_PATH).exists() {
-        Config::load_from_yaml_file(YAML_CONFIG_PATH)
-    } else if Path::new(YML_CONFIG_PATH).exists() {
-        Config::load_from_yaml_file(YML_CONFIG_PATH)
-    } else if Path::new(JSON_CONFIG_PATH).exists() {
-        Config::load_from_json_file(JSON_CONFIG_PATH)
-    } else {
-        println!("no config found, created config from world save");
-        Config::reverse_from_world_save(&running_world_save)
+    let config_layers = match Config::find_config_file(world_save_dir)
+        // if no config was found, try to load config from current directory
+        .or_else(|| Config::find_config_file(Path::new(".")))
+    {
+        Some(config_path) => ConfigLayers::new().with_file(&config_path)?,
+        None => {
+            println!("no config found, created config from world save");
+            ConfigLayers::new().with_world_save(&running_world_save)
+        }
     };
+    let running_config = config_layers
+        .with_environment()?
+        .with_flags(config_flags)?
+        .resolve()?;
 
-    (running_config, running_world_save)
-}
-
-fn load_config_from_yaml(config_path: &str) -> (Config, Save) {
-    let running_config = Config::load_from_yaml_file(config_path);
-    let running_world_save = Save::new(World::new(&running_config));
-    println!("loaded from yaml config");
-
-    (running_config, running_world_save)
+    Ok((running_config, running_world_save))
 }
 
-fn load_config_from_json(config_path: &str) -> (Config, Save) {
-    let running_config = Config::load_from_json_file(config_path);
-    let running_world_save = Save::new(World::new(&running_config));
-    println!("loaded from json config");
+fn load_config_from_file(
+    config_path: &Path,
+    config_flags: &[(String, String)],
+) -> Result<(Config, Save), String> {
+    let running_config = ConfigLayers::new()
+        .with_file(config_path)?
+        .with_environment()?
+        .with_flags(config_flags)?
+        .resolve()?;
+    let running_world_save = Save::new(new_world(&running_config)?);
+    println!("loaded from config: {:?}", config_path);
 
-    (running_config, running_world_save)
+    Ok((running_config, running_world_save))
 }
 
-fn print_help_message() {
-    println!("The Bouncy World Engine - v{}\n", env!("CARGO_PKG_VERSION"));
-    println!("Usage: bouncy-world.exe <COMMAND>\n");
-    println!("Commands:");
-    println!("  {}\t\t{}", NEW_COMMAND_LONG, NEW_COMMAND_DESCRIPTION);
-    println!("  {}\t\t{}", SAVE_COMMAND_LONG, SAVE_COMMAND_DESCRIPTION);
-    println!("  {}\t{}", CONFIG_COMMAND_LONG, CONFIG_COMMAND_DESCRIPTION);
-    println!("  {}\t\t{}", HELP_COMMAND_LONG, HELP_COMMAND_DESCRIPTION);
+// create a new world from the config, with a readable error when the config can not be spawned
+fn new_world(config: &Config) -> Result<World, String> {
+    World::new(config).map_err(|error| format!("could not create world: {}", error))
 }
@@ -0,0 +1,193 @@
+use clap::ValueHint;
+use clap_complete::{generate, Shell};
+
+use crate::{
+    cli::{self, CompletionShell},
+    constants::{CONFIG_PATHS, WORLD_SAVE_FILE_EXTENSION},
+};
+
+const BIN_NAME: &str = "bouncy-world";
+
+// print the completion script for the given shell, subcommands and flags come from the cli definition,
+// paths are completed to world saves and config files only
+pub fn print_completions(shell: CompletionShell) {
+    let mut script = vec![];
+    let clap_shell = match shell {
+        CompletionShell::Bash => Shell::Bash,
+        CompletionShell::Zsh => Shell::Zsh,
+        CompletionShell::Fish => Shell::Fish,
+    };
+    generate(clap_shell, &mut cli::command(), BIN_NAME, &mut script);
+    let script = String::from_utf8(script).expect("completion script is not valid utf-8");
+
+    let script = match shell {
+        CompletionShell::Bash => with_bash_path_filters(script),
+        CompletionShell::Zsh => with_zsh_path_filters(script),
+        CompletionShell::Fish => with_fish_path_filters(script),
+    };
+    print!("{}", script);
+}
+
+// file name patterns of config files, e.g. *config.yaml
+fn config_file_patterns() -> Vec<String> {
+    CONFIG_PATHS
+        .iter()
+        .map(|config_path| format!("*{}", config_path))
+        .collect()
+}
+
+// file name patterns of world saves and config files
+fn world_file_patterns() -> Vec<String> {
+    let mut patterns = vec![format!("*.{}", WORLD_SAVE_FILE_EXTENSION)];
+    patterns.extend(config_file_patterns());
+    patterns
+}
+
+// wrap the generated completion function, replacing plain file completion of paths
+fn with_bash_path_filters(script: String) -> String {
+    format!(
+        r#"{script}
+__bouncy__world__complete_files() {{
+    local cur="$1" pattern
+    shift
+    compopt -o filenames 2>/dev/null
+    COMPREPLY+=( $(compgen -d -- "${{cur}}") )
+    for pattern in "$@"; do
+        COMPREPLY+=( $(compgen -f -X "!${{pattern}}" -- "${{cur}}") )
+    done
+}}
+
+_bouncy__world__paths() {{
+    _bouncy__world "$@"
+    local cur="$2" prev="$3"
+    if [[ ${{cur}} == -* ]]; then
+        return 0
+    fi
+
+    if [[ ${{COMP_CWORD}} -eq 1 ]]; then
+        __bouncy__world__complete_files "${{cur}}" {world_file_patterns}
+    elif [[ ${{COMP_CWORD}} -eq 2 && ( ${{prev}} == run || ${{prev}} == r ) ]]; then
+        COMPREPLY=()
+        __bouncy__world__complete_files "${{cur}}" {world_file_patterns}
+    elif [[ ${{prev}} == --config || ${{prev}} == -c ]]; then
+        COMPREPLY=()
+        __bouncy__world__complete_files "${{cur}}" {config_file_patterns}
+    fi
+}}
+
+complete -F _bouncy__world__paths -o bashdefault -o default {BIN_NAME}
+"#,
+        world_file_patterns = single_quoted(&world_file_patterns()),
+        config_file_patterns = single_quoted(&config_file_patterns()),
+    )
+}
+
+// replace plain file completion of the world save and config file arguments with globs matching those files,
+// other paths (like the --stats output) keep completing any file
+fn with_zsh_path_filters(script: String) -> String {
+    let config_file_glob = format!("({})", CONFIG_PATHS.join("|"));
+    let world_file_glob = format!(
+        "(*.{}|{})",
+        WORLD_SAVE_FILE_EXTENSION,
+        CONFIG_PATHS.join("|")
+    );
+    let world_file_args: Vec<String> = world_file_arg_ids(&cli::command())
+        .iter()
+        .map(|arg_id| format!(":{} -- ", arg_id))
+        .collect();
+
+    // the --config option takes config files, the positional file arguments take world saves and config files
+    script
+        .lines()
+        .map(|line| {
+            if line.contains(":CONFIG:_files'") {
+                line.replace(
+                    ":CONFIG:_files'",
+                    &format!(":CONFIG:_files -g \"{}\"'", config_file_glob),
+                )
+            } else if world_file_args.iter().any(|arg| line.contains(arg)) {
+                line.replace(":_files'", &format!(":_files -g \"{}\"'", world_file_glob))
+            } else {
+                line.to_string()
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+        + "\n"
+}
+
+// the ids of the positional arguments of every command that take a file, those are world saves or config files
+fn world_file_arg_ids(command: &clap::Command) -> Vec<String> {
+    let mut arg_ids: Vec<String> = command
+        .get_positionals()
+        .filter(|arg| arg.get_value_hint() == ValueHint::FilePath)
+        .map(|arg| arg.get_id().to_string())
+        .collect();
+    for subcommand in command.get_subcommands() {
+        arg_ids.extend(world_file_arg_ids(subcommand));
+    }
+    arg_ids.sort();
+    arg_ids.dedup();
+    arg_ids
+}
+
+// add functions listing world saves and config files, and use them to complete paths
+fn with_fish_path_filters(script: String) -> String {
+    let script = script
+        .lines()
+        .map(|line| {
+            if line.contains(" -l config -d '") && line.ends_with(" -r -F") {
+                line.replace(
+                    " -r -F",
+                    " -r -f -a \"(__fish_bouncy_world_complete_config_files)\"",
+                )
+            } else {
+                line.to_string()
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    format!(
+        r#"{script}
+
+function __fish_bouncy_world_complete_world_files
+    set -l token (commandline -ct)
+    __fish_complete_directories $token
+    for file in {world_file_globs}
+        echo $file
+    end
+end
+
+function __fish_bouncy_world_complete_config_files
+    set -l token (commandline -ct)
+    __fish_complete_directories $token
+    for file in {config_file_globs}
+        echo $file
+    end
+end
+
+complete -c {BIN_NAME} -n "__fish_bouncy_world_needs_command" -f -a "(__fish_bouncy_world_complete_world_files)"
+complete -c {BIN_NAME} -n "__fish_bouncy_world_using_subcommand run r" -f -a "(__fish_bouncy_world_complete_world_files)"
+"#,
+        world_file_globs = fish_globs(&world_file_patterns()),
+        config_file_globs = fish_globs(&config_file_patterns()),
+    )
+}
+
+fn single_quoted(patterns: &[String]) -> String {
+    patterns
+        .iter()
+        .map(|pattern| format!("'{}'", pattern))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+// globs relative to the token being completed, e.g. $token*.bouncy-world
+fn fish_globs(patterns: &[String]) -> String {
+    patterns
+        .iter()
+        .map(|pattern| format!("$token{}", pattern))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
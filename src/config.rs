@@ -1,19 +1,33 @@
-use std::fs;
+use std::{
+    collections::HashMap,
+    env, fmt, fs,
+    path::{Path, PathBuf},
+};
 
 use serde_derive::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 
 use crate::{
     constants::{
-        DEBUG_MODE, DEFAULT_ENTITY_COLOR, DEFAULT_ENTITY_COUNT, DEFAULT_ENTITY_TYPE,
-        DEFAULT_ENTITY_VELOCITY, DEFAULT_WORLD_COLOR, DEFAULT_WORLD_HEIGHT, DEFAULT_WORLD_TYPE,
-        DEFAULT_WORLD_WIDTH,
+        CONFIG_FORMAT_VERSION, CONFIG_PATHS, DEBUG_MODE, DEFAULT_ENTITY_ANGLE,
+        DEFAULT_ENTITY_ANGULAR_VELOCITY, DEFAULT_ENTITY_COLOR, DEFAULT_ENTITY_COUNT,
+        DEFAULT_ENTITY_FIT, DEFAULT_ENTITY_IS_STATIC, DEFAULT_ENTITY_NO_OVERLAP,
+        DEFAULT_ENTITY_SIZE, DEFAULT_ENTITY_SPAWN, DEFAULT_ENTITY_SPIN_ON_BOUNCE,
+        DEFAULT_ENTITY_TYPE, DEFAULT_ENTITY_VELOCITY, DEFAULT_WORLD_BOUNDARY, DEFAULT_WORLD_COLOR,
+        DEFAULT_WORLD_HEIGHT, DEFAULT_WORLD_TYPE, DEFAULT_WORLD_WIDTH, ENVIRONMENT_VARIABLE_PREFIX,
     },
-    entity::EntityType,
-    save::Save,
-    world::WorldType,
+    emitter::Emitter,
+    entity::{ColorChange, EntityType, SpinOnBounce},
+    random::{RandomChoice, RandomNumber},
+    save::{self, Save},
+    spawn::{EntityFit, Spawn},
+    style::{Style, Trail},
+    world::{Boundary, WorldType},
 };
 
+// missing fields in a config file fall back to their default values
 #[derive(Deserialize, Serialize, Debug)]
+#[serde(default)]
 pub struct Config {
     pub is_debug_mode: bool,
 
@@ -21,13 +35,82 @@ pub struct Config {
     pub world_height: i32,
     pub world_color: String,
     pub world_type: WorldType,
+    // what every entity leaves behind as it moves, unless the entity has its own trail
+    pub world_trail: Trail,
+    // what happens to entities at the world edges: Bounce, Wrap, Despawn or Clamp
+    pub world_boundary: Boundary,
+    // used instead of world_boundary for the left and right edges
+    pub world_x_boundary: Option<Boundary>,
+    // used instead of world_boundary for the top and bottom edges
+    pub world_y_boundary: Option<Boundary>,
+    // spawn entities over time while the world runs
+    pub world_emitters: Vec<Emitter>,
 
     pub entity_count: i32,
-    pub entity_color: String,
+    pub entity_color: RandomChoice<String>,
     pub entity_type: EntityType,
-    pub entity_velocity: i32,
+    // scale of the entity type size, does not apply to images
+    pub entity_size: RandomNumber<f64>,
+    pub entity_velocity: RandomNumber<i32>,
+    // angle in degrees that entities start moving in, 0 is right and 90 is down,
+    // entities move diagonally in a random direction when not given
+    pub entity_direction: Option<RandomNumber<f64>>,
+    // where entities are placed when the world is created
+    pub entity_spawn: Spawn,
+    // pick random positions that do not overlap other entities
+    pub entity_no_overlap: bool,
+    // entities that never move, like walls and pegs, other entities bounce off them
+    pub entity_is_static: bool,
+    // seconds entities live before they despawn, entities live forever when not given
+    pub entity_lifetime: Option<RandomNumber<f64>>,
+    // how entities are drawn: outline, rounded corners, hollow and gradient
+    pub entity_style: Style,
+    // what entities leave behind as they move: Off, Fade(length) or Paint
+    pub entity_trail: Trail,
+    // what to do with entities that do not fit the world (or their spawn region)
+    pub entity_fit: EntityFit,
+    // angle in degrees that entities start turned by, clockwise
+    pub entity_angle: RandomNumber<i32>,
+    // degrees entities turn every frame, negative values turn counterclockwise
+    pub entity_angular_velocity: RandomNumber<i32>,
+    // how the spin of entities changes when they bounce off a world edge
+    pub entity_spin_on_bounce: SpinOnBounce,
+    // how the color of entities changes when they bounce off a world edge, images and sprites are tinted
+    pub entity_color_on_bounce: ColorChange,
+    // used instead of entity_color_on_bounce when entities hit a corner
+    pub entity_color_on_corner_hit: Option<ColorChange>,
+
+    // groups of entities, each with their own count, color, type and velocity,
+    // the entity_* fields above are only used when there are no entity groups
+    pub entity_groups: Vec<EntityGroup>,
 
     pub bouncy_world_engine_version: String,
+    // the version of the config format, older versions are read with defaults for the fields added since
+    #[serde(default = "save::legacy_format_version")]
+    pub format_version: u32,
+}
+
+// a group of entities that share a count, color, type and velocity
+#[derive(Clone, PartialEq, Deserialize, Serialize, Debug)]
+#[serde(default)]
+pub struct EntityGroup {
+    pub count: i32,
+    pub color: RandomChoice<String>,
+    pub entity_type: EntityType,
+    pub size: RandomNumber<f64>,
+    pub velocity: RandomNumber<i32>,
+    pub direction: Option<RandomNumber<f64>>,
+    pub spawn: Spawn,
+    pub no_overlap: bool,
+    pub is_static: bool,
+    pub lifetime: Option<RandomNumber<f64>>,
+    pub style: Style,
+    pub trail: Trail,
+    pub angle: RandomNumber<i32>,
+    pub angular_velocity: RandomNumber<i32>,
+    pub spin_on_bounce: SpinOnBounce,
+    pub color_on_bounce: ColorChange,
+    pub color_on_corner_hit: Option<ColorChange>,
 }
 
 impl Config {
@@ -38,15 +121,68 @@ impl Config {
             world_height: DEFAULT_WORLD_HEIGHT,
             world_color: DEFAULT_WORLD_COLOR.to_string(),
             world_type: DEFAULT_WORLD_TYPE,
+            world_trail: Trail::default(),
+            world_boundary: DEFAULT_WORLD_BOUNDARY,
+            world_x_boundary: None,
+            world_y_boundary: None,
+            world_emitters: vec![],
             entity_count: DEFAULT_ENTITY_COUNT,
-            entity_color: DEFAULT_ENTITY_COLOR.to_string(),
-            entity_velocity: DEFAULT_ENTITY_VELOCITY,
+            entity_color: RandomChoice::Value(DEFAULT_ENTITY_COLOR.to_string()),
+            entity_velocity: RandomNumber::Value(DEFAULT_ENTITY_VELOCITY),
             entity_type: DEFAULT_ENTITY_TYPE,
+            entity_size: RandomNumber::Value(DEFAULT_ENTITY_SIZE),
+            entity_direction: None,
+            entity_spawn: DEFAULT_ENTITY_SPAWN,
+            entity_no_overlap: DEFAULT_ENTITY_NO_OVERLAP,
+            entity_is_static: DEFAULT_ENTITY_IS_STATIC,
+            entity_lifetime: None,
+            entity_style: Style::default(),
+            entity_trail: Trail::default(),
+            entity_fit: DEFAULT_ENTITY_FIT,
+            entity_angle: RandomNumber::Value(DEFAULT_ENTITY_ANGLE),
+            entity_angular_velocity: RandomNumber::Value(DEFAULT_ENTITY_ANGULAR_VELOCITY),
+            entity_spin_on_bounce: DEFAULT_ENTITY_SPIN_ON_BOUNCE,
+            entity_color_on_bounce: ColorChange::default(),
+            entity_color_on_corner_hit: None,
+            entity_groups: vec![],
 
             bouncy_world_engine_version: env!("CARGO_PKG_VERSION").to_string(),
+            format_version: CONFIG_FORMAT_VERSION,
         }
     }
 
+    // the entity groups to create, the entity_* fields act as a single group when none are given
+    pub fn all_entity_groups(&self) -> Vec<EntityGroup> {
+        if !self.entity_groups.is_empty() {
+            return self.entity_groups.clone();
+        }
+
+        vec![EntityGroup {
+            count: self.entity_count,
+            color: self.entity_color.clone(),
+            entity_type: self.entity_type.clone(),
+            size: self.entity_size.clone(),
+            velocity: self.entity_velocity.clone(),
+            direction: self.entity_direction.clone(),
+            spawn: self.entity_spawn.clone(),
+            no_overlap: self.entity_no_overlap,
+            is_static: self.entity_is_static,
+            lifetime: self.entity_lifetime.clone(),
+            style: self.entity_style.clone(),
+            trail: self.entity_trail,
+            angle: self.entity_angle.clone(),
+            angular_velocity: self.entity_angular_velocity.clone(),
+            spin_on_bounce: self.entity_spin_on_bounce,
+            color_on_bounce: self.entity_color_on_bounce.clone(),
+            color_on_corner_hit: self.entity_color_on_corner_hit.clone(),
+        }]
+    }
+
+    // the names of all config fields
+    pub fn field_names() -> Vec<String> {
+        config_to_map(&Config::new()).keys().cloned().collect()
+    }
+
     pub fn reverse_from_world_save(world_save: &Save) -> Config {
         Config {
             // use default debug mode:
@@ -57,35 +193,411 @@ impl Config {
             world_height: world_save.world.height,
             world_color: world_save.world.background_rgba_hex.to_string(),
             world_type: world_save.world.world_type.clone(),
+            world_trail: world_save.world.trail,
+            world_boundary: world_save.world.boundary,
+            world_x_boundary: world_save.world.x_boundary,
+            world_y_boundary: world_save.world.y_boundary,
+            world_emitters: world_save.world.emitters.clone(),
 
             // only the entity count can be gotten from the world save
             entity_count: world_save.world.entities.len() as i32,
-            entity_color: DEFAULT_ENTITY_COLOR.to_string(),
-            entity_velocity: DEFAULT_ENTITY_VELOCITY,
+            entity_color: RandomChoice::Value(DEFAULT_ENTITY_COLOR.to_string()),
+            entity_velocity: RandomNumber::Value(DEFAULT_ENTITY_VELOCITY),
             entity_type: DEFAULT_ENTITY_TYPE,
+            entity_size: RandomNumber::Value(DEFAULT_ENTITY_SIZE),
+            entity_direction: None,
+            entity_spawn: DEFAULT_ENTITY_SPAWN,
+            entity_no_overlap: DEFAULT_ENTITY_NO_OVERLAP,
+            entity_is_static: DEFAULT_ENTITY_IS_STATIC,
+            entity_lifetime: None,
+            entity_style: Style::default(),
+            entity_trail: Trail::default(),
+            entity_fit: DEFAULT_ENTITY_FIT,
+            entity_angle: RandomNumber::Value(DEFAULT_ENTITY_ANGLE),
+            entity_angular_velocity: RandomNumber::Value(DEFAULT_ENTITY_ANGULAR_VELOCITY),
+            entity_spin_on_bounce: DEFAULT_ENTITY_SPIN_ON_BOUNCE,
+            entity_color_on_bounce: ColorChange::default(),
+            entity_color_on_corner_hit: None,
+            entity_groups: vec![],
 
             bouncy_world_engine_version: world_save.bouncy_world_engine_version.to_string(),
+            format_version: CONFIG_FORMAT_VERSION,
+        }
+    }
+
+    pub fn load_from_json_file(config_json_path: &str) -> Result<Config, String> {
+        let config_str = read_config_file(Path::new(config_json_path))?;
+        serde_json::from_str::<Config>(&config_str).map_err(|error| {
+            format!(
+                "could not read {:?} as a json config: {}",
+                config_json_path, error
+            )
+        })
+    }
+
+    pub fn save_to_json_file(&self, config_json_path: &str) -> Result<(), String> {
+        let config_str = serde_json::to_string_pretty(&self)
+            .map_err(|error| format!("could not write config as json: {}", error))?;
+        fs::write(config_json_path, config_str)
+            .map_err(|error| format!("could not write to file {:?}: {}", config_json_path, error))
+    }
+
+    pub fn load_from_yaml_file(config_yaml_path: &str) -> Result<Config, String> {
+        let config_str = read_config_file(Path::new(config_yaml_path))?;
+        serde_yaml::from_str::<Config>(&config_str).map_err(|error| {
+            format!(
+                "could not read {:?} as a yaml config: {}",
+                config_yaml_path, error
+            )
+        })
+    }
+
+    pub fn save_to_yaml_file(&self, config_yaml_path: &str) -> Result<(), String> {
+        let config_str = serde_yaml::to_string(&self)
+            .map_err(|error| format!("could not write config as yaml: {}", error))?;
+        fs::write(config_yaml_path, config_str)
+            .map_err(|error| format!("could not write to file {:?}: {}", config_yaml_path, error))
+    }
+
+    pub fn load_from_toml_file(config_toml_path: &str) -> Result<Config, String> {
+        let config_str = read_config_file(Path::new(config_toml_path))?;
+        toml::from_str::<Config>(&config_str).map_err(|error| {
+            format!(
+                "could not read {:?} as a toml config: {}",
+                config_toml_path, error
+            )
+        })
+    }
+
+    pub fn save_to_toml_file(&self, config_toml_path: &str) -> Result<(), String> {
+        let config_str = toml::to_string_pretty(&self)
+            .map_err(|error| format!("could not write config as toml: {}", error))?;
+        fs::write(config_toml_path, config_str)
+            .map_err(|error| format!("could not write to file {:?}: {}", config_toml_path, error))
+    }
+
+    // load a config file, the format is picked from the file extension
+    pub fn load_from_file(config_path: &Path) -> Result<Config, String> {
+        let config_path_str = config_path
+            .to_str()
+            .ok_or(format!("{:?} is not a valid path", config_path))?;
+        let config = match config_path
+            .extension()
+            .and_then(|extension| extension.to_str())
+        {
+            Some("yaml") | Some("yml") => Config::load_from_yaml_file(config_path_str)?,
+            Some("json") => Config::load_from_json_file(config_path_str)?,
+            Some("toml") => Config::load_from_toml_file(config_path_str)?,
+            _ => return Err(format!("unknown config file format: {:?}", config_path)),
+        };
+        if config.format_version > CONFIG_FORMAT_VERSION {
+            return Err(format!(
+                "{:?} has config format version {}, this engine reads up to version {}",
+                config_path, config.format_version, CONFIG_FORMAT_VERSION
+            ));
+        }
+        Ok(config)
+    }
+
+    // find the config file in the given directory, following the CONFIG_PATHS precedence:
+    // config.yaml, then config.yml, then config.json, then config.toml
+    pub fn find_config_file(dir: &Path) -> Option<PathBuf> {
+        let found_config_paths: Vec<PathBuf> = CONFIG_PATHS
+            .iter()
+            .map(|config_path| dir.join(config_path))
+            .filter(|config_path| config_path.exists())
+            .collect();
+
+        if found_config_paths.len() > 1 {
+            eprintln!(
+                "warning: found multiple config files {:?}, using {:?}",
+                found_config_paths, found_config_paths[0]
+            );
+        }
+
+        found_config_paths.into_iter().next()
+    }
+
+    // read the names of the fields that are set in a config file
+    fn load_field_names_from_file(config_path: &Path) -> Result<Vec<String>, String> {
+        let config_str = read_config_file(config_path)?;
+        match config_path
+            .extension()
+            .and_then(|extension| extension.to_str())
+        {
+            // json is valid yaml, so both are read as a yaml mapping
+            Some("yaml") | Some("yml") | Some("json") => Ok(serde_yaml::from_str::<
+                serde_yaml::Mapping,
+            >(&config_str)
+            .map_err(|error| format!("could not read {:?} as a config: {}", config_path, error))?
+            .keys()
+            .filter_map(|key| key.as_str().map(str::to_string))
+            .collect()),
+            Some("toml") => Ok(toml::from_str::<toml::Table>(&config_str)
+                .map_err(|error| {
+                    format!("could not read {:?} as a config: {}", config_path, error)
+                })?
+                .keys()
+                .cloned()
+                .collect()),
+            _ => Err(format!("unknown config file format: {:?}", config_path)),
+        }
+    }
+
+    pub fn is_config_file(path: &Path) -> bool {
+        CONFIG_PATHS
+            .iter()
+            .any(|config_path| path.ends_with(config_path))
+    }
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config::new()
+    }
+}
+
+impl EntityGroup {
+    // the name of the first random field with no choices to pick from, e.g. an empty list of colors
+    pub fn invalid_random_field(&self) -> Option<&'static str> {
+        [
+            ("color", self.color.is_valid()),
+            ("size", self.size.is_valid()),
+            ("velocity", self.velocity.is_valid()),
+            (
+                "direction",
+                self.direction.as_ref().is_none_or(RandomNumber::is_valid),
+            ),
+            ("angle", self.angle.is_valid()),
+            ("angular_velocity", self.angular_velocity.is_valid()),
+            (
+                "lifetime",
+                self.lifetime.as_ref().is_none_or(RandomNumber::is_valid),
+            ),
+        ]
+        .into_iter()
+        .find(|(_, is_valid)| !is_valid)
+        .map(|(field_name, _)| field_name)
+    }
+
+    pub fn new() -> EntityGroup {
+        EntityGroup {
+            count: DEFAULT_ENTITY_COUNT,
+            color: RandomChoice::Value(DEFAULT_ENTITY_COLOR.to_string()),
+            entity_type: DEFAULT_ENTITY_TYPE,
+            size: RandomNumber::Value(DEFAULT_ENTITY_SIZE),
+            velocity: RandomNumber::Value(DEFAULT_ENTITY_VELOCITY),
+            direction: None,
+            spawn: DEFAULT_ENTITY_SPAWN,
+            no_overlap: DEFAULT_ENTITY_NO_OVERLAP,
+            is_static: DEFAULT_ENTITY_IS_STATIC,
+            lifetime: None,
+            style: Style::default(),
+            trail: Trail::default(),
+            angle: RandomNumber::Value(DEFAULT_ENTITY_ANGLE),
+            angular_velocity: RandomNumber::Value(DEFAULT_ENTITY_ANGULAR_VELOCITY),
+            spin_on_bounce: DEFAULT_ENTITY_SPIN_ON_BOUNCE,
+            color_on_bounce: ColorChange::default(),
+            color_on_corner_hit: None,
+        }
+    }
+}
+
+impl Default for EntityGroup {
+    fn default() -> EntityGroup {
+        EntityGroup::new()
+    }
+}
+
+fn read_config_file(config_path: &Path) -> Result<String, String> {
+    fs::read_to_string(config_path)
+        .map_err(|error| format!("could not read from file {:?}: {}", config_path, error))
+}
+
+fn config_to_map(config: &Config) -> Map<String, Value> {
+    match serde_json::to_value(config).expect("could not convert config to a value") {
+        Value::Object(map) => map,
+        _ => unreachable!("config always converts to an object"),
+    }
+}
+
+// where a config value came from
+#[derive(Clone, Debug)]
+pub enum ConfigSource {
+    Default,
+    File(PathBuf),
+    WorldSave,
+    Environment(String),
+    Flag(String),
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigSource::Default => write!(f, "default"),
+            ConfigSource::File(config_path) => write!(f, "file {}", config_path.display()),
+            ConfigSource::WorldSave => write!(f, "world save"),
+            ConfigSource::Environment(variable) => write!(f, "environment {}", variable),
+            ConfigSource::Flag(flag) => write!(f, "flag {}", flag),
+        }
+    }
+}
+
+// a config built up from layers, each layer overrides the values of the layers before it:
+// defaults < config file (or world save) < environment variables < command line flags
+#[derive(Debug)]
+pub struct ConfigLayers {
+    values: Map<String, Value>,
+    sources: HashMap<String, ConfigSource>,
+}
+
+impl ConfigLayers {
+    pub fn new() -> ConfigLayers {
+        let values = config_to_map(&Config::new());
+        let sources = values
+            .keys()
+            .map(|field_name| (field_name.to_string(), ConfigSource::Default))
+            .collect();
+
+        ConfigLayers { values, sources }
+    }
+
+    // layer the fields that are set in the config file
+    pub fn with_file(mut self, config_path: &Path) -> Result<ConfigLayers, String> {
+        let file_values = config_to_map(&Config::load_from_file(config_path)?);
+        for field_name in Config::load_field_names_from_file(config_path)? {
+            // the format version of the file was checked when it was loaded, the resolved config is current
+            if field_name == "format_version" {
+                continue;
+            }
+            if let Some(value) = file_values.get(&field_name) {
+                self.set(
+                    &field_name,
+                    value.clone(),
+                    ConfigSource::File(config_path.to_path_buf()),
+                );
+            }
+        }
+
+        Ok(self)
+    }
+
+    // layer the fields that can be gotten from the world save
+    pub fn with_world_save(mut self, world_save: &Save) -> ConfigLayers {
+        let world_save_values = config_to_map(&Config::reverse_from_world_save(world_save));
+        for field_name in [
+            "world_width",
+            "world_height",
+            "world_color",
+            "world_type",
+            "world_trail",
+            "world_boundary",
+            "world_x_boundary",
+            "world_y_boundary",
+            "world_emitters",
+            "entity_count",
+            "bouncy_world_engine_version",
+        ] {
+            self.set(
+                field_name,
+                world_save_values[field_name].clone(),
+                ConfigSource::WorldSave,
+            );
+        }
+
+        self
+    }
+
+    // layer the BOUNCY_WORLD_* environment variables, e.g. BOUNCY_WORLD_WORLD_WIDTH=800
+    pub fn with_environment(mut self) -> Result<ConfigLayers, String> {
+        for field_name in Config::field_names() {
+            let variable = format!(
+                "{}{}",
+                ENVIRONMENT_VARIABLE_PREFIX,
+                field_name.to_uppercase()
+            );
+            if let Ok(raw_value) = env::var(&variable) {
+                self.set_raw(&field_name, &raw_value, ConfigSource::Environment(variable))?;
+            }
         }
+
+        Ok(self)
+    }
+
+    // layer the command line flags, given as (field_name, raw_value) pairs
+    pub fn with_flags(mut self, config_flags: &[(String, String)]) -> Result<ConfigLayers, String> {
+        for (field_name, raw_value) in config_flags {
+            let flag = format!("--{}", field_name.replace('_', "-"));
+            self.set_raw(field_name, raw_value, ConfigSource::Flag(flag))?;
+        }
+
+        Ok(self)
     }
 
-    pub fn load_from_json_file(config_json_path: &str) -> Config {
-        let config_str = fs::read_to_string(config_json_path).expect("could not read from file");
-        serde_json::from_str::<Config>(&config_str).expect("could not read config as json")
+    pub fn resolve(&self) -> Result<Config, String> {
+        serde_json::from_value(Value::Object(self.values.clone()))
+            .map_err(|error| format!("could not read config: {}", error))
     }
 
-    pub fn save_to_json_file(&self, config_json_path: &str) {
-        let config_str =
-            serde_json::to_string_pretty(&self).expect("could not write config as json");
-        std::fs::write(config_json_path, config_str).expect("could not write to file");
+    pub fn print_resolved(&self) {
+        for (field_name, value) in &self.values {
+            println!("{} = {}\t({})", field_name, value, self.sources[field_name]);
+        }
     }
 
-    pub fn load_from_yaml_file(config_yaml_path: &str) -> Config {
-        let config_str = fs::read_to_string(config_yaml_path).expect("could not read from file");
-        serde_yaml::from_str::<Config>(&config_str).expect("could not read config as yaml")
+    fn set(&mut self, field_name: &str, value: Value, source: ConfigSource) {
+        self.values.insert(field_name.to_string(), value);
+        self.sources.insert(field_name.to_string(), source);
     }
 
-    pub fn save_to_yaml_file(&self, config_yaml_path: &str) {
-        let config_str = serde_yaml::to_string(&self).expect("could not write config as yaml");
-        std::fs::write(config_yaml_path, config_str).expect("could not write to file");
+    // text fields take the raw value as is (unless it is a json list or object),
+    // every other field is read as json, fields that are null by default take text that is not json,
+    // e.g. Wrap for an optional boundary
+    fn set_raw(
+        &mut self,
+        field_name: &str,
+        raw_value: &str,
+        source: ConfigSource,
+    ) -> Result<(), String> {
+        let is_json_list_or_object = raw_value.starts_with('[') || raw_value.starts_with('{');
+        let value = match config_to_map(&Config::new()).get(field_name) {
+            Some(Value::String(_)) if !is_json_list_or_object => {
+                Value::String(raw_value.to_string())
+            }
+            Some(Value::Null) if !is_json_list_or_object => serde_json::from_str(raw_value)
+                .unwrap_or_else(|_| Value::String(raw_value.to_string())),
+            Some(_) => serde_json::from_str(raw_value).map_err(|error| {
+                format!(
+                    "could not read {} value {:?} as json: {}",
+                    source, raw_value, error
+                )
+            })?,
+            None => {
+                return Err(format!(
+                    "unknown config field {:?} from {}",
+                    field_name, source
+                ))
+            }
+        };
+
+        // check the value before it is used, so the error names where it came from
+        let previous_value = self.values.insert(field_name.to_string(), value);
+        if let Err(error) = self.resolve() {
+            if let Some(previous_value) = previous_value {
+                self.values.insert(field_name.to_string(), previous_value);
+            }
+            return Err(format!(
+                "invalid {} value {:?}: {}",
+                source, raw_value, error
+            ));
+        }
+        self.sources.insert(field_name.to_string(), source);
+        Ok(())
+    }
+}
+
+impl Default for ConfigLayers {
+    fn default() -> ConfigLayers {
+        ConfigLayers::new()
     }
 }
@@ -1,6 +1,15 @@
-use crate::{entity::EntityType, world::WorldType};
+use crate::{
+    entity::{EntityType, SpinOnBounce},
+    spawn::{EntityFit, Spawn},
+    world::{Boundary, WorldType},
+};
 
 // cli commands:
+// run command:
+pub const RUN_COMMAND_LONG: &str = "run";
+pub const RUN_COMMAND_SHORT: &str = "r";
+pub const RUN_COMMAND_DESCRIPTION: &str =
+    "runs a world save or config file (or the config in the current directory)";
 // new command:
 pub const NEW_COMMAND_LONG: &str = "new";
 pub const NEW_COMMAND_SHORT: &str = "n";
@@ -16,19 +25,71 @@ pub const CONFIG_COMMAND_LONG: &str = "config";
 pub const CONFIG_COMMAND_SHORT: &str = "c";
 pub const CONFIG_COMMAND_DESCRIPTION: &str =
     "generates a new default world configuration (if not already present)";
-// help command:
-pub const HELP_COMMAND_LONG: &str = "help";
-pub const HELP_COMMAND_SHORT: &str = "h";
-pub const HELP_COMMAND_DESCRIPTION: &str = "shows a list of all commands and their description";
+pub const CONFIG_SHOW_COMMAND: &str = "show";
+pub const CONFIG_SHOW_COMMAND_DESCRIPTION: &str = "shows the current world configuration";
+// version command:
+pub const VERSION_COMMAND_LONG: &str = "version";
+pub const VERSION_COMMAND_SHORT: &str = "v";
+pub const VERSION_COMMAND_DESCRIPTION: &str =
+    "shows the version, supported formats, features and git commit of The Bouncy World Engine";
+// list command:
+pub const LIST_COMMAND_LONG: &str = "list";
+pub const LIST_COMMAND_SHORT: &str = "l";
+pub const LIST_COMMAND_DESCRIPTION: &str = "lists the world saves (in the known-universe folder)";
+// inspect command:
+pub const INSPECT_COMMAND_LONG: &str = "inspect";
+pub const INSPECT_COMMAND_SHORT: &str = "i";
+pub const INSPECT_COMMAND_DESCRIPTION: &str =
+    "shows a detailed summary of a world save (entities, image assets and bounds problems)";
+// diff command:
+pub const DIFF_COMMAND_LONG: &str = "diff";
+pub const DIFF_COMMAND_SHORT: &str = "d";
+pub const DIFF_COMMAND_DESCRIPTION: &str =
+    "compares two world saves (world settings and added, removed and modified entities)";
+// merge command:
+pub const MERGE_COMMAND_LONG: &str = "merge";
+pub const MERGE_COMMAND_SHORT: &str = "m";
+pub const MERGE_COMMAND_DESCRIPTION: &str =
+    "combines the entities of several world saves into one world save (in the known-universe folder)";
+// predict command:
+pub const PREDICT_COMMAND_LONG: &str = "predict";
+pub const PREDICT_COMMAND_SHORT: &str = "p";
+pub const PREDICT_COMMAND_DESCRIPTION: &str =
+    "predicts the frame and corner of the next corner hit of every entity in a world save";
+// completions command:
+pub const COMPLETIONS_COMMAND_LONG: &str = "completions";
+pub const COMPLETIONS_COMMAND_DESCRIPTION: &str =
+    "generates a shell completion script (for bash, zsh or fish)";
+
+// save and config format versions, written to every world save and config file and bumped when a format
+// changes in a way older engines can't read, newer versions are rejected when a file is loaded
+pub const SAVE_FORMAT_VERSION: u32 = 2;
+pub const CONFIG_FORMAT_VERSION: u32 = 2;
+// the version of files written before the format version was recorded
+pub const LEGACY_FORMAT_VERSION: u32 = 1;
 
 // configuration files:
 pub const YAML_CONFIG_PATH: &str = "config.yaml";
 pub const YML_CONFIG_PATH: &str = "config.yml";
 pub const JSON_CONFIG_PATH: &str = "config.json";
+pub const TOML_CONFIG_PATH: &str = "config.toml";
+// configuration file precedence, the first one found in a directory is used
+pub const CONFIG_PATHS: [&str; 4] = [
+    YAML_CONFIG_PATH,
+    YML_CONFIG_PATH,
+    JSON_CONFIG_PATH,
+    TOML_CONFIG_PATH,
+];
+
+// prefix of the environment variables that override config values, e.g. BOUNCY_WORLD_WORLD_WIDTH
+pub const ENVIRONMENT_VARIABLE_PREFIX: &str = "BOUNCY_WORLD_";
 
 // bouncy-world save location and file extension
 pub const WORLD_SAVE_LOCATION: &str = "known-universe";
 pub const WORLD_SAVE_FILE_EXTENSION: &str = "bouncy-world";
+pub const WORLD_SAVE_THUMBNAIL_PATH: &str = "thumbnail.png";
+// the largest width or height of a world save thumbnail, in pixels
+pub const WORLD_SAVE_THUMBNAIL_SIZE: u32 = 160;
 // pub const IMAGE_SAVE_LOCATION: &str = "images";
 // pub const VIDEO_SAVE_LOCATION: &str = "videos";
 
@@ -40,8 +101,26 @@ pub const DEFAULT_WORLD_WIDTH: i32 = 600;
 pub const DEFAULT_WORLD_HEIGHT: i32 = 400;
 pub const DEFAULT_WORLD_COLOR: &str = "ffffffff";
 pub const DEFAULT_WORLD_TYPE: WorldType = WorldType::Color;
+pub const DEFAULT_WORLD_BOUNDARY: Boundary = Boundary::Bounce;
 // default entity settings
 pub const DEFAULT_ENTITY_COUNT: i32 = 1;
 pub const DEFAULT_ENTITY_VELOCITY: i32 = 5;
 pub const DEFAULT_ENTITY_COLOR: &str = "000000ff";
 pub const DEFAULT_ENTITY_TYPE: EntityType = EntityType::Box(50, 50);
+// scale of the entity type size
+pub const DEFAULT_ENTITY_SIZE: f64 = 1.0;
+pub const DEFAULT_ENTITY_SPAWN: Spawn = Spawn::Random;
+pub const DEFAULT_ENTITY_NO_OVERLAP: bool = false;
+pub const DEFAULT_ENTITY_IS_STATIC: bool = false;
+pub const DEFAULT_ENTITY_FIT: EntityFit = EntityFit::Error;
+// entities do not turn by default
+pub const DEFAULT_ENTITY_ANGLE: i32 = 0;
+pub const DEFAULT_ENTITY_ANGULAR_VELOCITY: i32 = 0;
+pub const DEFAULT_ENTITY_SPIN_ON_BOUNCE: SpinOnBounce = SpinOnBounce::Keep;
+// default emitter settings, entities per second and entities alive at the same time
+pub const DEFAULT_EMITTER_RATE: f64 = 1.0;
+pub const DEFAULT_EMITTER_MAX_POPULATION: i32 = 100;
+// how many frames the predict command steps the world to check its predictions
+pub const PREDICT_VERIFY_MAX_FRAMES: u64 = 1_000_000;
+// how many random positions are tried before placing an entity over another entity
+pub const SPAWN_ATTEMPTS: u32 = 100;
@@ -0,0 +1,187 @@
+use std::collections::BTreeSet;
+
+use serde_derive::Serialize;
+use serde_json::{Map, Value};
+
+use crate::{entity::Entity, save::Save};
+
+// a structural comparison of two world saves
+#[derive(Serialize, Debug)]
+pub struct SaveDiff {
+    pub world_changes: Vec<FieldChange>,
+    pub added_entities: Vec<EntityKey>,
+    pub removed_entities: Vec<EntityKey>,
+    pub modified_entities: Vec<EntityChange>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct FieldChange {
+    pub field: String,
+    pub old: Value,
+    pub new: Value,
+}
+
+#[derive(Serialize, Debug)]
+pub struct EntityChange {
+    #[serde(flatten)]
+    pub entity: EntityKey,
+    pub changes: Vec<FieldChange>,
+}
+
+// entities are matched by their id, entities without an id are matched by their index
+#[derive(Serialize, Clone, Debug)]
+pub struct EntityKey {
+    pub id: Option<String>,
+    pub index: usize,
+}
+
+impl SaveDiff {
+    pub fn new(old_save: &Save, new_save: &Save) -> SaveDiff {
+        let mut old_world = to_map(&old_save.world);
+        let mut new_world = to_map(&new_save.world);
+        old_world.remove("entities");
+        new_world.remove("entities");
+        let world_changes = field_changes(&old_world, &new_world);
+
+        let old_entities = keyed_entities(&old_save.world.entities);
+        let new_entities = keyed_entities(&new_save.world.entities);
+        let mut added_entities = vec![];
+        let mut removed_entities = vec![];
+        let mut modified_entities = vec![];
+
+        for (old_key, old_entity) in &old_entities {
+            match new_entities
+                .iter()
+                .find(|(new_key, _)| is_same_entity(old_key, new_key))
+            {
+                Some((_, new_entity)) => {
+                    let changes = field_changes(&to_map(*old_entity), &to_map(*new_entity));
+                    if !changes.is_empty() {
+                        modified_entities.push(EntityChange {
+                            entity: old_key.clone(),
+                            changes,
+                        });
+                    }
+                }
+                None => removed_entities.push(old_key.clone()),
+            }
+        }
+        for (new_key, _) in &new_entities {
+            if !old_entities
+                .iter()
+                .any(|(old_key, _)| is_same_entity(old_key, new_key))
+            {
+                added_entities.push(new_key.clone());
+            }
+        }
+
+        SaveDiff {
+            world_changes,
+            added_entities,
+            removed_entities,
+            modified_entities,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.world_changes.is_empty()
+            && self.added_entities.is_empty()
+            && self.removed_entities.is_empty()
+            && self.modified_entities.is_empty()
+    }
+
+    pub fn print(&self) {
+        if self.is_empty() {
+            println!("no differences");
+            return;
+        }
+
+        if !self.world_changes.is_empty() {
+            println!("world:");
+            for change in &self.world_changes {
+                change.print();
+            }
+        }
+
+        if !self.added_entities.is_empty()
+            || !self.removed_entities.is_empty()
+            || !self.modified_entities.is_empty()
+        {
+            println!("entities:");
+        }
+        for entity in &self.removed_entities {
+            println!("  - {}", entity);
+        }
+        for entity in &self.added_entities {
+            println!("  + {}", entity);
+        }
+        for entity_change in &self.modified_entities {
+            println!("  ~ {}", entity_change.entity);
+            for change in &entity_change.changes {
+                print!("  ");
+                change.print();
+            }
+        }
+    }
+}
+
+impl FieldChange {
+    fn print(&self) {
+        println!("  {}: {} -> {}", self.field, self.old, self.new);
+    }
+}
+
+impl std::fmt::Display for EntityKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match &self.id {
+            Some(id) => write!(f, "{} (#{})", id, self.index),
+            None => write!(f, "#{}", self.index),
+        }
+    }
+}
+
+fn is_same_entity(old_key: &EntityKey, new_key: &EntityKey) -> bool {
+    match (&old_key.id, &new_key.id) {
+        (Some(old_id), Some(new_id)) => old_id == new_id,
+        (None, None) => old_key.index == new_key.index,
+        _ => false,
+    }
+}
+
+fn keyed_entities(entities: &[Entity]) -> Vec<(EntityKey, &Entity)> {
+    entities
+        .iter()
+        .enumerate()
+        .map(|(index, entity)| {
+            let key = EntityKey {
+                id: entity.id.clone(),
+                index,
+            };
+            (key, entity)
+        })
+        .collect()
+}
+
+fn to_map<T: serde::Serialize>(value: &T) -> Map<String, Value> {
+    match serde_json::to_value(value).expect("could not convert to a value") {
+        Value::Object(map) => map,
+        _ => Map::new(),
+    }
+}
+
+// compare two objects field by field, a missing field is compared as null
+fn field_changes(old: &Map<String, Value>, new: &Map<String, Value>) -> Vec<FieldChange> {
+    let fields: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+    fields
+        .into_iter()
+        .filter_map(|field| {
+            let old_value = old.get(field).cloned().unwrap_or(Value::Null);
+            let new_value = new.get(field).cloned().unwrap_or(Value::Null);
+            (old_value != new_value).then(|| FieldChange {
+                field: field.to_string(),
+                old: old_value,
+                new: new_value,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,56 @@
+use std::time::Duration;
+
+use serde_derive::{Deserialize, Serialize};
+
+use crate::{
+    config::EntityGroup,
+    constants::{DEFAULT_EMITTER_MAX_POPULATION, DEFAULT_EMITTER_RATE},
+};
+
+// spawns entities over time while the world runs
+#[derive(Clone, PartialEq, Deserialize, Serialize, Debug)]
+#[serde(default)]
+pub struct Emitter {
+    // entities emitted per second
+    pub rate: f64,
+    // the most entities of this emitter alive at the same time, no entities are emitted while it is reached
+    pub max_population: i32,
+    // the emitted entities, spawn is where they are emitted from (a point with Positions or an area with Region),
+    // ranges of velocity and direction spread the emitted entities, the count is not used
+    pub entities: EntityGroup,
+    // entities due to be emitted, emitted once a whole entity is due
+    #[serde(skip)]
+    pub pending: f64,
+    // entities emitted since the world started running
+    #[serde(skip)]
+    pub emitted: u64,
+}
+
+impl Emitter {
+    pub fn new() -> Emitter {
+        Emitter {
+            rate: DEFAULT_EMITTER_RATE,
+            max_population: DEFAULT_EMITTER_MAX_POPULATION,
+            entities: EntityGroup::new(),
+            pending: 0.0,
+            emitted: 0,
+        }
+    }
+
+    // the number of entities to emit this frame, given the number of its entities still alive
+    pub fn due(&mut self, delta_time: Duration, population: i32) -> i32 {
+        self.pending += self.rate.max(0.0) * delta_time.as_secs_f64();
+        let count = (self.pending as i32)
+            .min(self.max_population - population)
+            .max(0);
+        // entities over the max population are not emitted later
+        self.pending = self.pending.fract();
+        count
+    }
+}
+
+impl Default for Emitter {
+    fn default() -> Emitter {
+        Emitter::new()
+    }
+}
@@ -1,6 +1,17 @@
+use std::{collections::VecDeque, f64::consts::PI, time::Duration};
+
+use hex::FromHex;
+use rand::Rng;
 use serde_derive::{Deserialize, Serialize};
 
-use crate::config::Config;
+use crate::{
+    config::EntityGroup,
+    predict::{self, AxisMotion, CornerPrediction},
+    random::RandomNumber,
+    stats::{Corner, CornerHit, EntityStats},
+    style::{self, is_false, is_zero, Gradient, Style, Trail},
+    world::Boundary,
+};
 
 // TODO: add note about ball only needing radius
 #[derive(Eq, PartialEq, Hash, Deserialize, Serialize, Clone, Debug)]
@@ -11,146 +22,1130 @@ pub enum EntityType {
     Image(String, i32, i32),
     // TODO: Text just acts as a Box, add ability to write text as pixels
     Text(i32, i32),
+    // x radius and y radius, positioned by its center like a Ball
+    Ellipse(i32, i32),
+    // number of sides and radius, positioned by its center, e.g. a triangle is RegularPolygon(3, 25)
+    RegularPolygon(i32, i32),
+    // vertices relative to the entity position, e.g. [(0, 0), (50, 0), (25, 40)]
+    Polygon(Vec<(i32, i32)>),
+    // end point relative to the entity position and thickness
+    Line(i32, i32, i32),
+    // an animated image, positioned by its top left corner, the image is either a sprite sheet
+    // (a grid of frames, played left to right and top to bottom) or a gif
+    Sprite {
+        path: String,
+        // the size of one frame
+        width: i32,
+        height: i32,
+        // frames shown per second
+        frame_rate: i32,
+        #[serde(default)]
+        loop_mode: AnimationLoop,
+        // the size of one frame in the sprite sheet when the sprite was shrunk to fit the world,
+        // the sprite shows the top left of each frame
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        frame_size: Option<(i32, i32)>,
+    },
+}
+
+// what a sprite does after its last frame
+#[derive(Clone, Copy, Default, Eq, PartialEq, Hash, Deserialize, Serialize, Debug)]
+pub enum AnimationLoop {
+    // start again from the first frame
+    #[default]
+    Loop,
+    // stay on the last frame
+    Once,
+    // play the frames backwards, then forwards again
+    PingPong,
+}
+
+// how the color of an entity changes when it bounces off a world edge,
+// images and sprites get a new tint instead
+#[derive(Clone, Default, Eq, PartialEq, Hash, Deserialize, Serialize, Debug)]
+pub enum ColorChange {
+    // keep the same color
+    #[default]
+    Keep,
+    // the color after the current color in the palette, the first color when the current color is not in it
+    Next(Vec<String>),
+    // a random opaque color
+    Random,
 }
 
-#[derive(Eq, PartialEq, Hash, Deserialize, Serialize, Debug)]
+// how the spin of an entity changes when it bounces off a world edge
+#[derive(Clone, Copy, Default, Eq, PartialEq, Hash, Deserialize, Serialize, Debug)]
+pub enum SpinOnBounce {
+    // keep spinning the same way
+    #[default]
+    Keep,
+    // spin the other way
+    Reverse,
+    // spin either way at a random speed up to the given degrees per frame
+    Random(i32),
+}
+
+#[derive(PartialEq, Deserialize, Serialize, Debug)]
 pub struct Entity {
+    // optional name of the entity, used to match entities when comparing world saves
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
     pub x_position: i32,
     pub y_position: i32,
     pub x_velocity: i32,
     pub y_velocity: i32,
     pub rgba_hex: String,
     pub entity_type: EntityType,
+    // static entities never move or turn, other entities bounce off them like walls
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub is_static: bool,
+    #[serde(default, skip_serializing_if = "Style::is_default")]
+    pub style: Style,
+    // orientation in degrees, turning clockwise around the center of the entity
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub angle: i32,
+    // degrees the entity turns every frame
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub angular_velocity: i32,
+    #[serde(default, skip_serializing_if = "SpinOnBounce::is_default")]
+    pub spin_on_bounce: SpinOnBounce,
+    #[serde(default, skip_serializing_if = "ColorChange::is_default")]
+    pub color_on_bounce: ColorChange,
+    // used instead of color_on_bounce when the entity hits a corner (bounces off two edges at once)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color_on_corner_hit: Option<ColorChange>,
+    // images and sprites are drawn in this color, keeping their transparency
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tint: Option<String>,
+    // what the entity leaves behind as it moves, the trail of the world is used when off
+    #[serde(default, skip_serializing_if = "Trail::is_default")]
+    pub trail: Trail,
+    // seconds the entity lives before it despawns, forever when not given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lifetime: Option<f64>,
+    // how long the entity has been animated (and alive), in milliseconds
+    #[serde(skip)]
+    pub animation_time: u64,
+    // the index of the emitter that spawned the entity, if any
+    #[serde(skip)]
+    pub emitter: Option<usize>,
+    // bounces, corner hits and distance since the world started running
+    #[serde(skip)]
+    pub stats: EntityStats,
+    // the last positions of the entity for its fading trail, oldest first
+    #[serde(skip)]
+    pub trail_positions: VecDeque<(i32, i32)>,
 }
 
-impl Entity {
-    pub fn new(config: &Config) -> Entity {
-        Entity {
-            // TODO: pull x and y positions from config? user might want to place on screen
-            x_position: {
-                let random = rand::random::<u32>();
-                match &config.entity_type {
-                    // TODO: handle this differently?
-                    EntityType::Box(width, _) => {
-                        (random % ((config.world_width - width) as u32)) as i32
-                    }
-                    EntityType::Ball(radius) => {
-                        (random % ((config.world_width - radius) as u32)) as i32
-                    }
-                    EntityType::Image(_, width, _) => {
-                        (random % ((config.world_width - width) as u32)) as i32
-                    }
-                    EntityType::Text(width, _) => {
-                        (random % ((config.world_width - width) as u32)) as i32
-                    }
-                }
-            },
-            y_position: {
-                let random = rand::random::<u32>();
-                match &config.entity_type {
-                    // TODO: handle this differently?
-                    EntityType::Box(_, height) => {
-                        (random % ((config.world_height - height) as u32)) as i32
-                    }
-                    EntityType::Ball(radius) => {
-                        (random % ((config.world_height - radius) as u32)) as i32
-                    }
-                    EntityType::Image(_, _, height) => {
-                        (random % ((config.world_height - height) as u32)) as i32
-                    }
-                    EntityType::Text(_, height) => {
-                        (random % ((config.world_height - height) as u32)) as i32
-                    }
-                }
-            },
-            x_velocity: config.entity_velocity * if rand::random() { 1 } else { -1 },
-            y_velocity: config.entity_velocity * if rand::random() { 1 } else { -1 },
-            rgba_hex: config.entity_color.to_string(),
-            entity_type: config.entity_type.clone(),
+// the bounds and outline of an entity, computed once per frame and used for every pixel the entity is drawn on
+pub struct EntityShape {
+    pub bounds: (i32, i32, i32, i32),
+    // the point the entity turns around and the sin and cos that turn pixels back with the entity,
+    // none when the entity is not turned
+    pivot: (f64, f64),
+    turn_back: Option<(f64, f64)>,
+    // the vertices of polygons relative to the entity position, as if the entity was not turned
+    vertices: Vec<(f64, f64)>,
+}
+
+impl EntityType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            EntityType::Box(_, _) => "Box",
+            EntityType::Ball(_) => "Ball",
+            EntityType::Image(_, _, _) => "Image",
+            EntityType::Text(_, _) => "Text",
+            EntityType::Ellipse(_, _) => "Ellipse",
+            EntityType::RegularPolygon(_, _) => "RegularPolygon",
+            EntityType::Polygon(_) => "Polygon",
+            EntityType::Line(_, _, _) => "Line",
+            EntityType::Sprite { .. } => "Sprite",
         }
     }
 
-    // update entity every frame
-    pub fn update(&mut self, config: &Config) {
-        match &self.entity_type {
-            EntityType::Box(width, height) => {
-                &self.width_and_height_bounce(*width, *height, config)
+    // the edges of the entity type relative to the entity position: (left, top, right, bottom),
+    // right and bottom exclusive
+    pub fn extents(&self) -> (i32, i32, i32, i32) {
+        match self {
+            EntityType::Box(width, height)
+            | EntityType::Image(_, width, height)
+            | EntityType::Text(width, height)
+            | EntityType::Sprite { width, height, .. } => (0, 0, *width, *height),
+            // balls and ellipses are positioned by their center pixel,
+            // pixels closer to the center than the radius are within the entity
+            EntityType::Ball(radius) => (1 - radius, 1 - radius, *radius, *radius),
+            EntityType::Ellipse(x_radius, y_radius) => {
+                (1 - x_radius, 1 - y_radius, *x_radius, *y_radius)
+            }
+            EntityType::RegularPolygon(_, _) | EntityType::Polygon(_) => {
+                points_extents(&self.vertices(), 0.0)
+            }
+            EntityType::Line(x_end, y_end, thickness) => points_extents(
+                &[(0.0, 0.0), (*x_end as f64, *y_end as f64)],
+                *thickness as f64 / 2.0,
+            ),
+        }
+    }
+
+    // the extents of the entity type turned by the angle in degrees around its pivot
+    pub fn rotated_extents(&self, angle: i32) -> (i32, i32, i32, i32) {
+        if angle.rem_euclid(360) == 0 {
+            return self.extents();
+        }
+        self.turned_extents(turn(angle))
+    }
+
+    // the extents of the entity type turned by the sin and cos of an angle, see turn()
+    fn turned_extents(&self, (sin, cos): (f64, f64)) -> (i32, i32, i32, i32) {
+        let pivot = self.pivot();
+        let rotate = |point: (f64, f64)| rotate_point(point, pivot, (sin, cos));
+        match self {
+            EntityType::Ball(_) => self.extents(),
+            EntityType::Ellipse(x_radius, y_radius) => {
+                let (x_radius, y_radius) = (*x_radius as f64, *y_radius as f64);
+                let x_extent = ((x_radius * cos).powi(2) + (y_radius * sin).powi(2))
+                    .sqrt()
+                    .ceil() as i32;
+                let y_extent = ((x_radius * sin).powi(2) + (y_radius * cos).powi(2))
+                    .sqrt()
+                    .ceil() as i32;
+                (1 - x_extent, 1 - y_extent, x_extent, y_extent)
             }
-            EntityType::Ball(radius) => &self.radius_bounce(*radius, config),
-            EntityType::Image(_, width, height) => {
-                &self.width_and_height_bounce(*width, *height, config)
+            EntityType::RegularPolygon(_, _) | EntityType::Polygon(_) => {
+                let vertices: Vec<(f64, f64)> = self.vertices().into_iter().map(rotate).collect();
+                points_extents(&vertices, 0.0)
             }
+            EntityType::Line(x_end, y_end, thickness) => points_extents(
+                &[rotate((0.0, 0.0)), rotate((*x_end as f64, *y_end as f64))],
+                *thickness as f64 / 2.0,
+            ),
+            // the pixels of rectangles cover half a pixel around each position
+            EntityType::Box(_, _)
+            | EntityType::Image(_, _, _)
+            | EntityType::Text(_, _)
+            | EntityType::Sprite { .. } => {
+                let (width, height) = self.size();
+                let (right, bottom) = (width as f64 - 0.5, height as f64 - 0.5);
+                let corners =
+                    [(-0.5, -0.5), (right, -0.5), (-0.5, bottom), (right, bottom)].map(rotate);
+                let min = |values: &mut dyn Iterator<Item = f64>| values.fold(f64::MAX, f64::min);
+                let max = |values: &mut dyn Iterator<Item = f64>| values.fold(f64::MIN, f64::max);
+                (
+                    min(&mut corners.iter().map(|corner| corner.0)).ceil() as i32,
+                    min(&mut corners.iter().map(|corner| corner.1)).ceil() as i32,
+                    max(&mut corners.iter().map(|corner| corner.0)).ceil() as i32,
+                    max(&mut corners.iter().map(|corner| corner.1)).ceil() as i32,
+                )
+            }
+        }
+    }
+
+    // the point the entity type turns around, relative to the entity position
+    fn pivot(&self) -> (f64, f64) {
+        match self {
+            // centered shapes turn around their center pixel
+            EntityType::Ball(_) | EntityType::Ellipse(_, _) | EntityType::RegularPolygon(_, _) => {
+                (0.0, 0.0)
+            }
+            _ => {
+                let (left, top, right, bottom) = self.extents();
+                (
+                    (left + right - 1) as f64 / 2.0,
+                    (top + bottom - 1) as f64 / 2.0,
+                )
+            }
+        }
+    }
+
+    // the width and height of the entity type
+    pub fn size(&self) -> (i32, i32) {
+        let (left, top, right, bottom) = self.extents();
+        (right - left, bottom - top)
+    }
+
+    // false for shapes that can not be drawn, like polygons with less than 3 sides
+    pub fn is_valid_shape(&self) -> bool {
+        let (width, height) = self.size();
+        let has_size = match self {
+            // a line can be a single pixel wide
+            EntityType::Line(_, _, thickness) => *thickness > 0,
+            _ => width > 0 && height > 0,
+        };
+        has_size
+            && match self {
+                EntityType::RegularPolygon(sides, radius) => *sides >= 3 && *radius > 0,
+                EntityType::Polygon(vertices) => vertices.len() >= 3,
+                EntityType::Box(_, _)
+                | EntityType::Ball(_)
+                | EntityType::Image(_, _, _)
+                | EntityType::Text(_, _)
+                | EntityType::Ellipse(_, _)
+                | EntityType::Line(_, _, _)
+                | EntityType::Sprite { .. } => true,
+            }
+    }
+
+    // the entity type with its size scaled, images and sprites keep their size as they are drawn pixel by pixel
+    pub fn scaled(&self, scale: f64) -> EntityType {
+        let scale_size = |size: i32| ((size as f64 * scale).round() as i32).max(1);
+        let scale_offset = |offset: i32| (offset as f64 * scale).round() as i32;
+        match self {
+            EntityType::Box(width, height) => {
+                EntityType::Box(scale_size(*width), scale_size(*height))
+            }
+            EntityType::Ball(radius) => EntityType::Ball(scale_size(*radius)),
+            EntityType::Image(_, _, _) | EntityType::Sprite { .. } => self.clone(),
             EntityType::Text(width, height) => {
-                &self.width_and_height_bounce(*width, *height, config)
+                EntityType::Text(scale_size(*width), scale_size(*height))
+            }
+            EntityType::Ellipse(x_radius, y_radius) => {
+                EntityType::Ellipse(scale_size(*x_radius), scale_size(*y_radius))
             }
+            EntityType::RegularPolygon(sides, radius) => {
+                EntityType::RegularPolygon(*sides, scale_size(*radius))
+            }
+            EntityType::Polygon(vertices) => EntityType::Polygon(
+                vertices
+                    .iter()
+                    .map(|(x, y)| (scale_offset(*x), scale_offset(*y)))
+                    .collect(),
+            ),
+            EntityType::Line(x_end, y_end, thickness) => EntityType::Line(
+                scale_offset(*x_end),
+                scale_offset(*y_end),
+                scale_size(*thickness),
+            ),
+        }
+    }
+
+    // the corners of polygons relative to the entity position, regular polygons point up
+    fn vertices(&self) -> Vec<(f64, f64)> {
+        match self {
+            EntityType::RegularPolygon(sides, radius) => (0..*sides)
+                .map(|side| {
+                    let angle = -PI / 2.0 + 2.0 * PI * side as f64 / *sides as f64;
+                    (*radius as f64 * angle.cos(), *radius as f64 * angle.sin())
+                })
+                .collect(),
+            EntityType::Polygon(vertices) => vertices
+                .iter()
+                .map(|(x, y)| (*x as f64, *y as f64))
+                .collect(),
+            _ => vec![],
+        }
+    }
+}
+
+impl AnimationLoop {
+    // the frame to show after the given number of frames have passed
+    fn frame(&self, frames_passed: u64, frame_count: u64) -> u64 {
+        match self {
+            AnimationLoop::Loop => frames_passed % frame_count,
+            AnimationLoop::Once => frames_passed.min(frame_count - 1),
+            AnimationLoop::PingPong if frame_count == 1 => 0,
+            AnimationLoop::PingPong => {
+                let round_trip = 2 * (frame_count - 1);
+                let frame = frames_passed % round_trip;
+                frame.min(round_trip - frame)
+            }
+        }
+    }
+}
+
+impl ColorChange {
+    pub fn is_default(&self) -> bool {
+        *self == ColorChange::default()
+    }
+
+    // the color after a bounce
+    fn rgba_hex(&self, rgba_hex: &str) -> String {
+        match self {
+            ColorChange::Next(palette) if !palette.is_empty() => {
+                let index = palette
+                    .iter()
+                    .position(|color| color == rgba_hex)
+                    .map_or(0, |index| (index + 1) % palette.len());
+                palette[index].to_string()
+            }
+            ColorChange::Random => {
+                let [red, green, blue]: [u8; 3] = rand::random();
+                format!("{:02x}{:02x}{:02x}ff", red, green, blue)
+            }
+            ColorChange::Keep | ColorChange::Next(_) => rgba_hex.to_string(),
+        }
+    }
+}
+
+impl SpinOnBounce {
+    pub fn is_default(&self) -> bool {
+        *self == SpinOnBounce::default()
+    }
+
+    // the angular velocity after a bounce
+    fn angular_velocity(&self, angular_velocity: i32) -> i32 {
+        match self {
+            SpinOnBounce::Keep => angular_velocity,
+            SpinOnBounce::Reverse => -angular_velocity,
+            SpinOnBounce::Random(max) => {
+                // a spin of more than a full turn per frame looks the same as a smaller one,
+                // unsigned_abs does not overflow on i32::MIN
+                let max = max.unsigned_abs().min(360) as i32;
+                rand::thread_rng().gen_range(-max..=max)
+            }
+        }
+    }
+}
+
+impl Entity {
+    pub fn new(entity_group: &EntityGroup) -> Entity {
+        let entity_type = entity_group.entity_type.scaled(entity_group.size.pick());
+        let velocity = if entity_group.is_static {
+            0
+        } else {
+            entity_group.velocity.pick()
+        };
+        let (x_velocity, y_velocity) = match &entity_group.direction {
+            Some(direction) => {
+                let angle = direction.pick().to_radians();
+                (
+                    (velocity as f64 * angle.cos()).round() as i32,
+                    (velocity as f64 * angle.sin()).round() as i32,
+                )
+            }
+            None => (
+                velocity * if rand::random() { 1 } else { -1 },
+                velocity * if rand::random() { 1 } else { -1 },
+            ),
+        };
+
+        Entity {
+            id: None,
+            // entities are placed by the world, see World::new()
+            x_position: 0,
+            y_position: 0,
+            x_velocity,
+            y_velocity,
+            rgba_hex: entity_group.color.pick(),
+            entity_type,
+            is_static: entity_group.is_static,
+            style: entity_group.style.clone(),
+            angle: entity_group.angle.pick(),
+            angular_velocity: entity_group.angular_velocity.pick(),
+            spin_on_bounce: entity_group.spin_on_bounce,
+            color_on_bounce: entity_group.color_on_bounce.clone(),
+            color_on_corner_hit: entity_group.color_on_corner_hit.clone(),
+            tint: None,
+            lifetime: entity_group.lifetime.as_ref().map(RandomNumber::pick),
+            animation_time: 0,
+            emitter: None,
+            stats: EntityStats::default(),
+            trail: entity_group.trail,
+            trail_positions: VecDeque::new(),
+        }
+    }
+
+    // update entity every frame, delta_time is the time since the last update
+    pub fn update(
+        &mut self,
+        world_size: (i32, i32),
+        x_boundary: Boundary,
+        y_boundary: Boundary,
+        delta_time: Duration,
+    ) {
+        self.animation_time += delta_time.as_millis() as u64;
+        self.stats.frames_alive += 1;
+        if self.is_static {
+            return;
+        }
+        self.angle = (self.angle + self.angular_velocity).rem_euclid(360);
+
+        // the distance moved before crossing an edge, so wrapping around the world is not counted
+        self.stats.distance += (self.x_velocity as f64).hypot(self.y_velocity as f64);
+        self.bounce(world_size, x_boundary, y_boundary);
+    }
+
+    // whether the entity lived past its lifetime
+    pub fn is_expired(&self) -> bool {
+        self.lifetime
+            .is_some_and(|lifetime| self.animation_time as f64 >= lifetime * 1000.0)
+    }
+
+    // remember the current position for a fading trail of the given length
+    pub fn record_trail_position(&mut self, length: usize) {
+        self.trail_positions
+            .push_back((self.x_position, self.y_position));
+        while self.trail_positions.len() > length {
+            self.trail_positions.pop_front();
+        }
+    }
+
+    // the position of the current sprite frame in the sprite sheet of the given size
+    pub fn sprite_frame_position(&self, sheet_width: u32, sheet_height: u32) -> (i32, i32) {
+        let EntityType::Sprite {
+            width,
+            height,
+            frame_rate,
+            loop_mode,
+            frame_size,
+            ..
+        } = &self.entity_type
+        else {
+            return (0, 0);
+        };
+
+        // sprites from world saves are not checked, a frame is at least 1 pixel
+        let (width, height) = frame_size.unwrap_or((*width, *height));
+        let (width, height) = (width.max(1), height.max(1));
+        let columns = (sheet_width as i32 / width).max(1);
+        let rows = (sheet_height as i32 / height).max(1);
+        let frames_passed = self.animation_time * (*frame_rate).max(0) as u64 / 1000;
+        let frame = loop_mode.frame(frames_passed, (columns * rows) as u64) as i32;
+        (frame % columns * width, frame / columns * height)
+    }
+
+    // the color of an image or sprite pixel, drawn in the tint color when there is one
+    pub fn tinted_rgba_hex(&self, image_rgba_hex: String) -> String {
+        match &self.tint {
+            // tints from world saves are not checked, a tint that is not a rgba hex value is not drawn
+            Some(tint) if <[u8; 4]>::from_hex(tint).is_ok() => {
+                format!("{}{}", &tint[..6], &image_rgba_hex[6..])
+            }
+            _ => image_rgba_hex,
+        }
+    }
+
+    // the frame and corner of the next corner hit, computed from the velocity of the entity and the world size
+    pub fn next_corner_hit(
+        &self,
+        world_width: i32,
+        world_height: i32,
+        x_boundary: Boundary,
+        y_boundary: Boundary,
+    ) -> CornerPrediction {
+        // only moving entities bouncing off the world edges can hit a corner
+        if self.is_static || x_boundary != Boundary::Bounce || y_boundary != Boundary::Bounce {
+            return CornerPrediction::Never;
+        }
+
+        let is_spinning = self.angular_velocity != 0
+            || matches!(self.spin_on_bounce, SpinOnBounce::Random(max) if max != 0);
+        if is_spinning {
+            return CornerPrediction::Unpredictable(
+                "spinning entities change their bounds as they turn".to_string(),
+            );
+        }
+
+        if self.lifetime.is_some() {
+            return CornerPrediction::Unpredictable(
+                "entities with a lifetime despawn after a time, not a number of frames".to_string(),
+            );
+        }
+
+        let (left, top, right, bottom) = self.bounds();
+        if right - left > world_width || bottom - top > world_height {
+            return CornerPrediction::Unpredictable(
+                "the entity is larger than the world".to_string(),
+            );
+        }
+
+        let x_motion = AxisMotion::new(left, right - left, world_width, self.x_velocity);
+        let y_motion = AxisMotion::new(top, bottom - top, world_height, self.y_velocity);
+        match predict::next_common_bounce(x_motion, y_motion) {
+            Some((frames, is_right, is_bottom)) => CornerPrediction::Hit(CornerHit {
+                frame: self.stats.frames_alive + frames,
+                corner: Corner::new(is_right, is_bottom),
+            }),
+            None => CornerPrediction::Never,
+        }
+    }
+
+    // the shape of the entity at its current position and angle, see EntityShape
+    pub fn shape(&self) -> EntityShape {
+        let is_turned = self.angle.rem_euclid(360) != 0;
+        let (sin, cos) = turn(self.angle);
+        let (left, top, right, bottom) = if is_turned {
+            self.entity_type.turned_extents((sin, cos))
+        } else {
+            self.entity_type.extents()
         };
+        EntityShape {
+            bounds: (
+                self.x_position + left,
+                self.y_position + top,
+                self.x_position + right,
+                self.y_position + bottom,
+            ),
+            pivot: self.entity_type.pivot(),
+            turn_back: is_turned.then_some((-sin, cos)),
+            vertices: self.entity_type.vertices(),
+        }
+    }
+
+    // the bounding box of the entity: (left, top, right, bottom), right and bottom exclusive
+    pub fn bounds(&self) -> (i32, i32, i32, i32) {
+        let (left, top, right, bottom) = self.entity_type.rotated_extents(self.angle);
+        (
+            self.x_position + left,
+            self.y_position + top,
+            self.x_position + right,
+            self.y_position + bottom,
+        )
+    }
+
+    // a position relative to the entity position, as if the entity was not turned
+    pub fn local_offset(
+        &self,
+        shape: &EntityShape,
+        x_position: i32,
+        y_position: i32,
+    ) -> (f64, f64) {
+        let offset = (
+            (x_position - self.x_position) as f64,
+            (y_position - self.y_position) as f64,
+        );
+        match shape.turn_back {
+            Some(turn_back) => rotate_point(offset, shape.pivot, turn_back),
+            None => offset,
+        }
     }
 
     // draw entity every frame
     // drawing of entity is done is world::draw()
     // pub fn draw(&self, frame: &mut [u8]) {}
 
-    pub fn is_within_entity(&self, x_position: i32, y_position: i32) -> bool {
-        match self.entity_type {
-            EntityType::Box(width, height) => {
-                self.is_within_width_and_height(x_position, y_position, width, height)
+    pub fn is_within_entity(&self, shape: &EntityShape, x_position: i32, y_position: i32) -> bool {
+        let (left, top, right, bottom) = shape.bounds;
+        if x_position < left || x_position >= right || y_position < top || y_position >= bottom {
+            return false;
+        }
+
+        // position relative to the entity position, turned back with the entity
+        let (x_offset, y_offset) = self.local_offset(shape, x_position, y_position);
+        match &self.entity_type {
+            EntityType::Box(width, height) | EntityType::Text(width, height) => {
+                let (x_offset, y_offset) = (x_offset.round(), y_offset.round());
+                is_within_size(x_offset, y_offset, *width, *height)
+                    && self.is_within_rounded_corners(x_offset, y_offset, *width, *height)
             }
-            EntityType::Ball(radius) => self.is_within_radius(x_position, y_position, radius),
-            EntityType::Image(_, width, height) => {
-                self.is_within_width_and_height(x_position, y_position, width, height)
+            EntityType::Image(_, width, height) | EntityType::Sprite { width, height, .. } => {
+                is_within_size(x_offset.round(), y_offset.round(), *width, *height)
             }
-            EntityType::Text(width, height) => {
-                self.is_within_width_and_height(x_position, y_position, width, height)
+            EntityType::Ball(radius) => self.is_within_radius(x_position, y_position, *radius),
+            EntityType::Ellipse(x_radius, y_radius) => {
+                (x_offset / *x_radius as f64).powi(2) + (y_offset / *y_radius as f64).powi(2) < 1.0
+            }
+            EntityType::RegularPolygon(_, _) | EntityType::Polygon(_) => {
+                is_within_polygon(x_offset, y_offset, &shape.vertices)
+            }
+            EntityType::Line(x_end, y_end, thickness) => {
+                distance_to_line(
+                    x_offset,
+                    y_offset,
+                    (0.0, 0.0),
+                    (*x_end as f64, *y_end as f64),
+                ) <= *thickness as f64 / 2.0
             }
         }
     }
 
-    fn width_and_height_bounce(&mut self, width: i32, height: i32, config: &Config) {
-        if self.x_position <= 0 || self.x_position + width > config.world_width {
-            self.x_velocity *= -1;
-        }
+    // the color of a pixel within the entity, using the entity style,
+    // none for pixels that are not drawn like the inside of hollow entities
+    pub fn styled_rgba_hex(
+        &self,
+        shape: &EntityShape,
+        x_position: i32,
+        y_position: i32,
+    ) -> Option<String> {
+        let style = &self.style;
+        let (x_offset, y_offset) = self.local_offset(shape, x_position, y_position);
 
-        if self.y_position <= 0 || self.y_position + height > config.world_height {
-            self.y_velocity *= -1;
+        let outline_width = style.outline_width();
+        if outline_width > 0 && self.edge_distance(shape, x_offset, y_offset) < outline_width as f64
+        {
+            return Some(
+                style
+                    .stroke_rgba_hex
+                    .clone()
+                    .unwrap_or_else(|| self.rgba_hex.to_string()),
+            );
+        }
+        if style.hollow {
+            return None;
         }
 
-        self.x_position += self.x_velocity;
-        self.y_position += self.y_velocity;
+        // gradients turn with the entity
+        let (left, top, right, bottom) = self.entity_type.extents();
+        Some(match &style.gradient {
+            None => self.rgba_hex.to_string(),
+            Some(Gradient::Linear(to_rgba_hex, angle)) => {
+                // project the pixel and the corners of the entity on the gradient direction
+                let angle = (*angle as f64).to_radians();
+                let project = |x: i32, y: i32| x as f64 * angle.cos() + y as f64 * angle.sin();
+                let corners = [
+                    project(left, top),
+                    project(right - 1, top),
+                    project(left, bottom - 1),
+                    project(right - 1, bottom - 1),
+                ];
+                let start = corners.iter().cloned().fold(f64::MAX, f64::min);
+                let end = corners.iter().cloned().fold(f64::MIN, f64::max);
+                let pixel = x_offset * angle.cos() + y_offset * angle.sin();
+                let amount = if end > start {
+                    (pixel - start) / (end - start)
+                } else {
+                    0.0
+                };
+                style::mix_rgba_hex(&self.rgba_hex, to_rgba_hex, amount)
+            }
+            Some(Gradient::Radial(to_rgba_hex)) => {
+                let x_center = (left + right - 1) as f64 / 2.0;
+                let y_center = (top + bottom - 1) as f64 / 2.0;
+                let half_width = ((right - left) as f64 / 2.0).max(1.0);
+                let half_height = ((bottom - top) as f64 / 2.0).max(1.0);
+                let amount = (((x_offset - x_center) / half_width).powi(2)
+                    + ((y_offset - y_center) / half_height).powi(2))
+                .sqrt();
+                style::mix_rgba_hex(&self.rgba_hex, to_rgba_hex, amount)
+            }
+        })
     }
 
-    fn radius_bounce(&mut self, radius: i32, config: &Config) {
-        if self.x_position - radius <= 0 || self.x_position + radius > config.world_width {
-            self.x_velocity *= -1;
+    // how far a point within the entity is from its edge, relative to the entity position
+    fn edge_distance(&self, shape: &EntityShape, x_offset: f64, y_offset: f64) -> f64 {
+        match &self.entity_type {
+            EntityType::Box(width, height)
+            | EntityType::Image(_, width, height)
+            | EntityType::Text(width, height)
+            | EntityType::Sprite { width, height, .. } => {
+                let (right, bottom) = ((width - 1) as f64, (height - 1) as f64);
+                match self.rounded_corner_center(x_offset, y_offset, *width, *height) {
+                    Some((x_center, y_center, radius)) => {
+                        radius
+                            - ((x_offset - x_center).powi(2) + (y_offset - y_center).powi(2)).sqrt()
+                    }
+                    None => x_offset
+                        .min(right - x_offset)
+                        .min(y_offset)
+                        .min(bottom - y_offset),
+                }
+            }
+            EntityType::Ball(radius) => {
+                *radius as f64 - (x_offset.powi(2) + y_offset.powi(2)).sqrt()
+            }
+            // an estimate, exact for circles
+            EntityType::Ellipse(x_radius, y_radius) => {
+                (1.0 - ((x_offset / *x_radius as f64).powi(2)
+                    + (y_offset / *y_radius as f64).powi(2))
+                .sqrt())
+                    * (*x_radius).min(*y_radius) as f64
+            }
+            EntityType::RegularPolygon(_, _) | EntityType::Polygon(_) => {
+                let vertices = &shape.vertices;
+                (0..vertices.len())
+                    .map(|index| {
+                        let end = vertices[(index + 1) % vertices.len()];
+                        distance_to_line(x_offset, y_offset, vertices[index], end)
+                    })
+                    .fold(f64::MAX, f64::min)
+            }
+            EntityType::Line(x_end, y_end, thickness) => {
+                *thickness as f64 / 2.0
+                    - distance_to_line(
+                        x_offset,
+                        y_offset,
+                        (0.0, 0.0),
+                        (*x_end as f64, *y_end as f64),
+                    )
+            }
         }
-        if self.y_position - radius <= 0 || self.y_position + radius > config.world_height {
-            self.y_velocity *= -1;
+    }
+
+    // the center and radius of the rounded corner the point is in, if any
+    fn rounded_corner_center(
+        &self,
+        x_offset: f64,
+        y_offset: f64,
+        width: i32,
+        height: i32,
+    ) -> Option<(f64, f64, f64)> {
+        let radius = self.style.corner_radius.min(width / 2).min(height / 2) as f64;
+        if radius <= 0.0 {
+            return None;
         }
 
-        self.x_position += self.x_velocity;
-        self.y_position += self.y_velocity;
+        let (right, bottom) = ((width - 1) as f64, (height - 1) as f64);
+        let x_center = if x_offset < radius {
+            radius
+        } else if x_offset > right - radius {
+            right - radius
+        } else {
+            return None;
+        };
+        let y_center = if y_offset < radius {
+            radius
+        } else if y_offset > bottom - radius {
+            bottom - radius
+        } else {
+            return None;
+        };
+        Some((x_center, y_center, radius))
     }
 
-    fn is_within_width_and_height(
+    fn is_within_rounded_corners(
         &self,
-        x_position: i32,
-        y_position: i32,
+        x_offset: f64,
+        y_offset: f64,
         width: i32,
         height: i32,
     ) -> bool {
-        x_position >= self.x_position
-            && x_position < self.x_position + width
-            && y_position >= self.y_position
-            && y_position < self.y_position + height
+        match self.rounded_corner_center(x_offset, y_offset, width, height) {
+            Some((x_center, y_center, radius)) => {
+                (x_offset - x_center).powi(2) + (y_offset - y_center).powi(2) <= radius.powi(2)
+            }
+            None => true,
+        }
+    }
+
+    // move and bounce off the world edges using the edges of the entity, bouncing and clamping
+    // entities that went past an edge are moved back inside the world, so they are never drawn outside it,
+    // world_size is the width and height of the world the entity lives in, not the config it came from
+    fn bounce(&mut self, world_size: (i32, i32), x_boundary: Boundary, y_boundary: Boundary) {
+        let (world_width, world_height) = world_size;
+        self.x_position += self.x_velocity;
+        self.y_position += self.y_velocity;
+
+        let (left, top, right, bottom) = self.bounds();
+        let is_x_bounce = x_boundary == Boundary::Bounce && (left < 0 || right > world_width);
+        let is_y_bounce = y_boundary == Boundary::Bounce && (top < 0 || bottom > world_height);
+        (self.x_position, self.x_velocity) = cross_edge(
+            x_boundary,
+            self.x_position,
+            self.x_velocity,
+            (left, right),
+            world_width,
+        );
+        (self.y_position, self.y_velocity) = cross_edge(
+            y_boundary,
+            self.y_position,
+            self.y_velocity,
+            (top, bottom),
+            world_height,
+        );
+
+        if is_x_bounce || is_y_bounce {
+            let corner = Corner::new(right > world_width, bottom > world_height);
+            self.stats.record_bounce(is_x_bounce, is_y_bounce, corner);
+            // bouncing off two edges in the same frame is a corner hit
+            self.react_to_bounce(is_x_bounce && is_y_bounce);
+        }
     }
 
+    // bounce off the static entities the entity moved into, using the bounding boxes of both,
+    // previous_bounds are the bounds of the entity before it moved this frame
+    pub fn bounce_off_obstacles(
+        &mut self,
+        previous_bounds: (i32, i32, i32, i32),
+        obstacles: &[(i32, i32, i32, i32)],
+    ) {
+        let (previous_left, previous_top, previous_right, previous_bottom) = previous_bounds;
+        let mut is_bounce = false;
+        for (obstacle_left, obstacle_top, obstacle_right, obstacle_bottom) in obstacles {
+            let (left, top, right, bottom) = self.bounds();
+            // the part of the frame the entity overlaps the obstacle on each axis, so fast entities
+            // that move through the obstacle in a single frame still bounce off it
+            let (x_entry, x_exit) = overlap_times(
+                (previous_left, previous_right),
+                left - previous_left,
+                (*obstacle_left, *obstacle_right),
+            );
+            let (y_entry, y_exit) = overlap_times(
+                (previous_top, previous_bottom),
+                top - previous_top,
+                (*obstacle_top, *obstacle_bottom),
+            );
+            let entry = x_entry.max(y_entry);
+            // entities that were already inside the obstacle are left to move out
+            if !(0.0..1.0).contains(&entry) || entry >= x_exit.min(y_exit) {
+                continue;
+            }
+
+            // the entity bounces off the side it reached last, both sides when it hit a corner
+            if x_entry == entry {
+                if previous_right <= *obstacle_left {
+                    self.x_position -= right - obstacle_left;
+                    self.x_velocity = -self.x_velocity.abs();
+                } else {
+                    self.x_position += obstacle_right - left;
+                    self.x_velocity = self.x_velocity.abs();
+                }
+            }
+            if y_entry == entry {
+                if previous_bottom <= *obstacle_top {
+                    self.y_position -= bottom - obstacle_top;
+                    self.y_velocity = -self.y_velocity.abs();
+                } else {
+                    self.y_position += obstacle_bottom - top;
+                    self.y_velocity = self.y_velocity.abs();
+                }
+            }
+            is_bounce = true;
+        }
+
+        if is_bounce {
+            self.stats.obstacle_bounces += 1;
+            self.react_to_bounce(false);
+        }
+    }
+
+    // change the spin and color after a bounce
+    fn react_to_bounce(&mut self, is_corner_hit: bool) {
+        self.angular_velocity = self.spin_on_bounce.angular_velocity(self.angular_velocity);
+        let color_change = match &self.color_on_corner_hit {
+            Some(color_change) if is_corner_hit => color_change.clone(),
+            _ => self.color_on_bounce.clone(),
+        };
+        self.change_color(&color_change);
+    }
+
+    // images and sprites are tinted instead of changing the entity color
+    fn change_color(&mut self, color_change: &ColorChange) {
+        if color_change.is_default() {
+            return;
+        }
+
+        match &self.entity_type {
+            EntityType::Image(_, _, _) | EntityType::Sprite { .. } => {
+                let tint = self.tint.as_deref().unwrap_or(&self.rgba_hex);
+                self.tint = Some(color_change.rgba_hex(tint));
+            }
+            _ => self.rgba_hex = color_change.rgba_hex(&self.rgba_hex),
+        }
+    }
+
+    // the ball is centered on the entity position
     fn is_within_radius(&self, x_position: i32, y_position: i32, radius: i32) -> bool {
-        let distance = {
-            let x_distance = x_position - self.x_position;
-            let y_distance = y_position - self.y_position;
-            ((x_distance.pow(2) + y_distance.pow(2)) as f64)
-                .sqrt()
-                .powi(2)
+        let x_distance = x_position - self.x_position;
+        let y_distance = y_position - self.y_position;
+        x_distance.pow(2) + y_distance.pow(2) < radius.pow(2)
+    }
+}
+
+// the position and velocity of one axis of an entity after moving, edges are the entity edges on that axis,
+// despawning entities are removed by the world
+fn cross_edge(
+    boundary: Boundary,
+    position: i32,
+    velocity: i32,
+    edges: (i32, i32),
+    world_size: i32,
+) -> (i32, i32) {
+    let (start, end) = edges;
+    match boundary {
+        Boundary::Bounce | Boundary::Clamp if start < 0 || end > world_size => {
+            let position = if start < 0 {
+                position - start
+            } else {
+                position - (end - world_size)
+            };
+            let velocity = match boundary {
+                Boundary::Clamp => 0,
+                _ if start < 0 => velocity.abs(),
+                _ => -velocity.abs(),
+            };
+            (position, velocity)
+        }
+        // the entity comes back in once it is completely past the edge
+        Boundary::Wrap if start >= world_size => (position - world_size, velocity),
+        Boundary::Wrap if end <= 0 => (position + world_size, velocity),
+        _ => (position, velocity),
+    }
+}
+
+// the part of a frame (0 is the start, 1 the end) in which an edge span moving by distance overlaps
+// the obstacle span on one axis, from the time it starts overlapping to the time it stops
+fn overlap_times(edges: (i32, i32), distance: i32, obstacle_edges: (i32, i32)) -> (f64, f64) {
+    let (start, end) = edges;
+    let (obstacle_start, obstacle_end) = obstacle_edges;
+    let distance = distance as f64;
+    if distance > 0.0 {
+        (
+            (obstacle_start - end) as f64 / distance,
+            (obstacle_end - start) as f64 / distance,
+        )
+    } else if distance < 0.0 {
+        (
+            (obstacle_end - start) as f64 / distance,
+            (obstacle_start - end) as f64 / distance,
+        )
+    } else if start < obstacle_end && obstacle_start < end {
+        (f64::NEG_INFINITY, f64::INFINITY)
+    } else {
+        (f64::INFINITY, f64::INFINITY)
+    }
+}
+
+// the pixels of a rectangle from the entity position
+fn is_within_size(x_offset: f64, y_offset: f64, width: i32, height: i32) -> bool {
+    x_offset >= 0.0 && y_offset >= 0.0 && x_offset < width as f64 && y_offset < height as f64
+}
+
+// the sin and cos of an angle in degrees, computed once to turn many points by the angle
+fn turn(angle: i32) -> (f64, f64) {
+    (angle as f64).to_radians().sin_cos()
+}
+
+// turn a point clockwise around the pivot by the sin and cos of an angle, see turn()
+fn rotate_point(point: (f64, f64), pivot: (f64, f64), (sin, cos): (f64, f64)) -> (f64, f64) {
+    let (x, y) = (point.0 - pivot.0, point.1 - pivot.1);
+    (pivot.0 + x * cos - y * sin, pivot.1 + x * sin + y * cos)
+}
+
+// the extents of the pixels within the distance of the points, right and bottom exclusive,
+// the first pixel is the first whole position at or after the smallest point
+fn points_extents(points: &[(f64, f64)], distance: f64) -> (i32, i32, i32, i32) {
+    let min = |values: &mut dyn Iterator<Item = f64>| values.fold(f64::MAX, f64::min);
+    let max = |values: &mut dyn Iterator<Item = f64>| values.fold(f64::MIN, f64::max);
+    (
+        (min(&mut points.iter().map(|point| point.0)) - distance).ceil() as i32,
+        (min(&mut points.iter().map(|point| point.1)) - distance).ceil() as i32,
+        (max(&mut points.iter().map(|point| point.0)) + distance).floor() as i32 + 1,
+        (max(&mut points.iter().map(|point| point.1)) + distance).floor() as i32 + 1,
+    )
+}
+
+// even-odd rule: a point is inside when a ray from it crosses the polygon edges an odd number of times
+fn is_within_polygon(x: f64, y: f64, vertices: &[(f64, f64)]) -> bool {
+    let mut is_within = false;
+    for (index, (x_start, y_start)) in vertices.iter().enumerate() {
+        let (x_end, y_end) = vertices[(index + 1) % vertices.len()];
+        if (*y_start > y) != (y_end > y)
+            && x < x_start + (y - y_start) * (x_end - x_start) / (y_end - y_start)
+        {
+            is_within = !is_within;
+        }
+    }
+    is_within
+}
+
+// the distance from a point to the line between the start and end points
+fn distance_to_line(x: f64, y: f64, start: (f64, f64), end: (f64, f64)) -> f64 {
+    let (x, y) = (x - start.0, y - start.1);
+    let (x_end, y_end) = (end.0 - start.0, end.1 - start.1);
+    let length_squared = x_end.powi(2) + y_end.powi(2);
+    // how far along the line the closest point is, from 0 (start) to 1 (end)
+    let along = if length_squared == 0.0 {
+        0.0
+    } else {
+        ((x * x_end + y * y_end) / length_squared).clamp(0.0, 1.0)
+    };
+    ((x - along * x_end).powi(2) + (y - along * y_end).powi(2)).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::Config,
+        random::RandomNumber,
+        spawn::{self, EntityFit, Spawn},
+        world::World,
+    };
+
+    #[test]
+    fn random_spin_on_bounce_accepts_any_max() {
+        for max in [i32::MIN, -7, 0, 7, i32::MAX] {
+            let angular_velocity = SpinOnBounce::Random(max).angular_velocity(0);
+            assert!(angular_velocity.abs() <= max.unsigned_abs().min(360) as i32);
+        }
+    }
+
+    #[test]
+    fn balls_never_leave_the_world() {
+        let mut config = Config::new();
+        config.world_width = 97;
+        config.world_height = 61;
+        config.entity_groups = [1, 2, 3, 10, 30]
+            .into_iter()
+            .map(|radius| EntityGroup {
+                count: 4,
+                entity_type: EntityType::Ball(radius),
+                velocity: RandomNumber::Range { min: 1, max: 13 },
+                ..EntityGroup::new()
+            })
+            .collect();
+        let mut world = World::new(&config).expect("balls fit the world");
+
+        for frame in 0..5000 {
+            world.update(&config, Duration::ZERO);
+            for entity in &world.entities {
+                let (left, top, right, bottom) = entity.bounds();
+                assert!(
+                    left >= 0 && top >= 0 && right <= 97 && bottom <= 61,
+                    "{:?} at frame {} is outside the world: {:?}",
+                    entity.entity_type,
+                    frame,
+                    (left, top, right, bottom)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn fast_entities_bounce_off_thin_obstacles() {
+        let mut config = Config::new();
+        config.world_width = 60;
+        config.world_height = 40;
+        config.entity_type = EntityType::Box(4, 4);
+        let mut world = World::new(&config).expect("a 4x4 box fits the world");
+
+        let mut wall = Entity::new(&EntityGroup {
+            entity_type: EntityType::Box(4, 40),
+            is_static: true,
+            ..EntityGroup::new()
+        });
+        wall.x_position = 40;
+        wall.y_position = 0;
+        // moving 9 pixels a frame, the box is past the 4 pixel wall after its first frame
+        let mut entity = Entity::new(&EntityGroup {
+            entity_type: EntityType::Box(4, 4),
+            ..EntityGroup::new()
+        });
+        (entity.x_position, entity.y_position) = (35, 18);
+        (entity.x_velocity, entity.y_velocity) = (9, 0);
+        world.entities = vec![wall, entity];
+
+        for frame in 0..3 {
+            world.update(&config, Duration::ZERO);
+            let (_, _, right, _) = world.entities[1].bounds();
+            assert!(
+                right <= 40,
+                "the box went through the wall at frame {}",
+                frame
+            );
+        }
+        assert_eq!(world.entities[1].x_velocity, -9);
+        assert_eq!(world.entities[1].stats.obstacle_bounces, 1);
+    }
+
+    #[test]
+    fn shrunk_sprites_show_the_frames_of_the_sprite_sheet() {
+        // a sprite sheet of 2 frames of 40x40, side by side
+        let sprite = EntityType::Sprite {
+            path: "sprite.png".to_string(),
+            width: 40,
+            height: 40,
+            frame_rate: 1,
+            loop_mode: AnimationLoop::Loop,
+            frame_size: None,
         };
-        distance < (radius as f64).powi(2)
+        let mut entity = Entity::new(&EntityGroup::new());
+        entity.entity_type =
+            spawn::fit_entity_type(&sprite, &Spawn::Random, EntityFit::Shrink, 30, 25)
+                .expect("sprites can be shrunk");
+        assert_eq!(entity.entity_type.size(), (30, 25));
+
+        assert_eq!(entity.sprite_frame_position(80, 40), (0, 0));
+        entity.animation_time = 1000;
+        assert_eq!(entity.sprite_frame_position(80, 40), (40, 0));
     }
 }
@@ -0,0 +1,375 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde_derive::Serialize;
+
+use crate::{
+    constants::WORLD_SAVE_FILE_EXTENSION,
+    entity::{Entity, EntityType},
+    save::Save,
+    world::WorldType,
+};
+
+// a short description of a world save, used by the list command
+#[derive(Serialize, Debug)]
+pub struct SaveSummary {
+    pub path: PathBuf,
+    pub date_time_stamp: String,
+    pub bouncy_world_engine_version: String,
+    pub format_version: u32,
+    pub title: Option<String>,
+    pub tags: Vec<String>,
+    pub world_width: i32,
+    pub world_height: i32,
+    pub entity_count: usize,
+    pub entity_types: BTreeMap<String, usize>,
+}
+
+// a detailed description of a world save, used by the inspect command
+#[derive(Serialize, Debug)]
+pub struct SaveInspection {
+    #[serde(flatten)]
+    pub summary: SaveSummary,
+    pub author: Option<String>,
+    pub description: Option<String>,
+    pub created_at: Option<String>,
+    pub modified_at: Option<String>,
+    pub thumbnail: Option<PathBuf>,
+    pub world_type: String,
+    pub background_rgba_hex: String,
+    pub entities: Vec<EntitySummary>,
+    pub image_assets: Vec<ImageAsset>,
+    pub problems: Vec<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct EntitySummary {
+    pub index: usize,
+    pub entity_type: String,
+    pub x_position: i32,
+    pub y_position: i32,
+    pub x_velocity: i32,
+    pub y_velocity: i32,
+    pub rgba_hex: String,
+    // (left, top, right, bottom)
+    pub bounds: (i32, i32, i32, i32),
+}
+
+#[derive(Serialize, Debug)]
+pub struct ImageAsset {
+    pub path: String,
+    pub used_by: String,
+    pub width: i32,
+    pub height: i32,
+    pub exists: bool,
+    // the size of the image file, if it could be read
+    pub actual_size: Option<(u32, u32)>,
+}
+
+impl SaveSummary {
+    pub fn new(save_path: &Path, world_save: &Save) -> SaveSummary {
+        let mut entity_types = BTreeMap::new();
+        for entity in &world_save.world.entities {
+            *entity_types
+                .entry(entity.entity_type.name().to_string())
+                .or_insert(0) += 1;
+        }
+
+        SaveSummary {
+            path: save_path.to_path_buf(),
+            date_time_stamp: world_save.date_time_stamp.to_string(),
+            bouncy_world_engine_version: world_save.bouncy_world_engine_version.to_string(),
+            format_version: world_save.format_version,
+            title: world_save.title.clone(),
+            tags: world_save.tags.clone(),
+            world_width: world_save.world.width,
+            world_height: world_save.world.height,
+            entity_count: world_save.world.entities.len(),
+            entity_types,
+        }
+    }
+
+    // e.g. "Ball: 1, Box: 2"
+    pub fn entity_types_str(&self) -> String {
+        self.entity_types
+            .iter()
+            .map(|(entity_type, count)| format!("{}: {}", entity_type, count))
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+}
+
+impl SaveInspection {
+    pub fn new(save_path: &Path, world_save: &Save) -> SaveInspection {
+        let world = &world_save.world;
+
+        let entities = world
+            .entities
+            .iter()
+            .enumerate()
+            .map(|(index, entity)| EntitySummary {
+                index,
+                entity_type: entity.entity_type.name().to_string(),
+                x_position: entity.x_position,
+                y_position: entity.y_position,
+                x_velocity: entity.x_velocity,
+                y_velocity: entity.y_velocity,
+                rgba_hex: entity.rgba_hex.to_string(),
+                bounds: entity.bounds(),
+            })
+            .collect();
+
+        let mut image_assets = vec![];
+        if let WorldType::Image(image_path, width, height) = &world.world_type {
+            image_assets.push(ImageAsset::new(image_path, "world", *width, *height));
+        }
+        for (index, entity) in world.entities.iter().enumerate() {
+            if let EntityType::Image(image_path, width, height)
+            | EntityType::Sprite {
+                path: image_path,
+                width,
+                height,
+                ..
+            } = &entity.entity_type
+            {
+                let used_by = format!("entity {}", index);
+                image_assets.push(ImageAsset::new(image_path, &used_by, *width, *height));
+            }
+        }
+
+        let mut problems = vec![];
+        for (index, entity) in world.entities.iter().enumerate() {
+            if let Some(problem) = entity_bounds_problem(entity, world.width, world.height) {
+                problems.push(format!(
+                    "entity {} ({}) {}",
+                    index,
+                    entity.entity_type.name(),
+                    problem
+                ));
+            }
+        }
+        for image_asset in &image_assets {
+            problems.extend(image_asset.problem());
+        }
+        if let WorldType::Image(_, width, height) = &world.world_type {
+            if *width < world.width || *height < world.height {
+                problems.push(format!(
+                    "world image ({}x{}) is smaller than the world ({}x{})",
+                    width, height, world.width, world.height
+                ));
+            }
+        }
+
+        SaveInspection {
+            summary: SaveSummary::new(save_path, world_save),
+            author: world_save.author.clone(),
+            description: world_save.description.clone(),
+            created_at: world_save.created_at.clone(),
+            modified_at: world_save.modified_at.clone(),
+            // the thumbnail path is relative to the world save
+            thumbnail: world_save
+                .thumbnail
+                .as_ref()
+                .map(|thumbnail| save_path.parent().unwrap_or(Path::new("")).join(thumbnail)),
+            world_type: match &world.world_type {
+                WorldType::Color => "Color".to_string(),
+                WorldType::Image(image_path, _, _) => format!("Image ({})", image_path),
+            },
+            background_rgba_hex: world.background_rgba_hex.to_string(),
+            entities,
+            image_assets,
+            problems,
+        }
+    }
+
+    pub fn print(&self) {
+        let summary = &self.summary;
+        println!("{}", summary.path.display());
+        if let Some(title) = &summary.title {
+            println!("  title: {}", title);
+        }
+        if let Some(author) = &self.author {
+            println!("  author: {}", author);
+        }
+        if let Some(description) = &self.description {
+            println!("  description: {}", description);
+        }
+        if !summary.tags.is_empty() {
+            println!("  tags: {}", summary.tags.join(", "));
+        }
+        println!(
+            "  created: {}",
+            self.created_at.as_ref().unwrap_or(&summary.date_time_stamp)
+        );
+        if let Some(modified_at) = &self.modified_at {
+            println!("  modified: {}", modified_at);
+        }
+        if let Some(thumbnail) = &self.thumbnail {
+            println!("  thumbnail: {}", thumbnail.display());
+        }
+        println!(
+            "  engine version: {} (format version {})",
+            summary.bouncy_world_engine_version, summary.format_version
+        );
+        println!(
+            "  world: {}x{} {} {}",
+            summary.world_width, summary.world_height, self.world_type, self.background_rgba_hex
+        );
+
+        println!(
+            "  entities: {} ({})",
+            summary.entity_count,
+            summary.entity_types_str()
+        );
+        for entity in &self.entities {
+            println!(
+                "    #{} {} at ({}, {}) velocity ({}, {}) color {}",
+                entity.index,
+                entity.entity_type,
+                entity.x_position,
+                entity.y_position,
+                entity.x_velocity,
+                entity.y_velocity,
+                entity.rgba_hex
+            );
+        }
+
+        if !self.image_assets.is_empty() {
+            println!("  image assets:");
+            for image_asset in &self.image_assets {
+                let status = match (image_asset.exists, image_asset.actual_size) {
+                    (false, _) => "missing".to_string(),
+                    (true, Some((width, height))) => format!("found {}x{}", width, height),
+                    (true, None) => "unreadable".to_string(),
+                };
+                println!(
+                    "    {} ({}, {}x{}): {}",
+                    image_asset.path,
+                    image_asset.used_by,
+                    image_asset.width,
+                    image_asset.height,
+                    status
+                );
+            }
+        }
+
+        if self.problems.is_empty() {
+            println!("  no problems found");
+        } else {
+            println!("  problems:");
+            for problem in &self.problems {
+                println!("    {}", problem);
+            }
+        }
+    }
+}
+
+impl ImageAsset {
+    fn new(image_path: &str, used_by: &str, width: i32, height: i32) -> ImageAsset {
+        let exists = Path::new(image_path).exists();
+        ImageAsset {
+            path: image_path.to_string(),
+            used_by: used_by.to_string(),
+            width,
+            height,
+            exists,
+            actual_size: if exists {
+                image::image_dimensions(image_path).ok()
+            } else {
+                None
+            },
+        }
+    }
+
+    fn problem(&self) -> Option<String> {
+        match self.actual_size {
+            _ if !self.exists => Some(format!(
+                "image {} ({}) does not exist",
+                self.path, self.used_by
+            )),
+            None => Some(format!(
+                "image {} ({}) could not be read",
+                self.path, self.used_by
+            )),
+            // drawing reads pixels up to the given size, a smaller image can not be drawn
+            Some((width, height))
+                if (width as i32) < self.width || (height as i32) < self.height =>
+            {
+                Some(format!(
+                    "image {} ({}) is {}x{}, smaller than the given {}x{}",
+                    self.path, self.used_by, width, height, self.width, self.height
+                ))
+            }
+            Some(_) => None,
+        }
+    }
+}
+
+fn entity_bounds_problem(entity: &Entity, world_width: i32, world_height: i32) -> Option<String> {
+    let (left, top, right, bottom) = entity.bounds();
+    if right - left > world_width || bottom - top > world_height {
+        Some(format!(
+            "is larger than the world: {}x{} in a {}x{} world",
+            right - left,
+            bottom - top,
+            world_width,
+            world_height
+        ))
+    } else if right <= 0 || bottom <= 0 || left >= world_width || top >= world_height {
+        Some(format!(
+            "is outside the world: ({}, {}) to ({}, {})",
+            left, top, right, bottom
+        ))
+    } else if left < 0 || top < 0 || right > world_width || bottom > world_height {
+        Some(format!(
+            "is partly outside the world: ({}, {}) to ({}, {})",
+            left, top, right, bottom
+        ))
+    } else {
+        None
+    }
+}
+
+// find the world save file at the given path, the path can be the save file or its directory
+pub fn find_save_file(path: &Path) -> Result<PathBuf, String> {
+    if !path.is_dir() {
+        return Ok(path.to_path_buf());
+    }
+
+    let save_paths: Vec<PathBuf> = fs::read_dir(path)
+        .map_err(|error| format!("could not read directory {:?}: {}", path, error))?
+        .filter_map(|save_file| save_file.ok().map(|save_file| save_file.path()))
+        .filter(|save_path| {
+            save_path
+                .extension()
+                .is_some_and(|extension| extension == WORLD_SAVE_FILE_EXTENSION)
+        })
+        .collect();
+    match save_paths.as_slice() {
+        [save_path] => Ok(save_path.to_path_buf()),
+        [] => Err(format!("no world save found in {:?}", path)),
+        _ => Err(format!("more than one world save found in {:?}", path)),
+    }
+}
+
+pub fn print_save_summaries(save_summaries: &[SaveSummary]) {
+    println!(
+        "{:<20} {:<24} {:<8} {:<10} {:<9} {:<24} PATH",
+        "CREATED", "TITLE", "VERSION", "SIZE", "ENTITIES", "TYPES"
+    );
+    for save_summary in save_summaries {
+        println!(
+            "{:<20} {:<24} {:<8} {:<10} {:<9} {:<24} {}",
+            save_summary.date_time_stamp,
+            save_summary.title.as_deref().unwrap_or("-"),
+            save_summary.bouncy_world_engine_version,
+            format!("{}x{}", save_summary.world_width, save_summary.world_height),
+            save_summary.entity_count,
+            save_summary.entity_types_str(),
+            save_summary.path.display()
+        );
+    }
+}
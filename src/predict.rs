@@ -0,0 +1,405 @@
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use serde_derive::Serialize;
+
+use crate::{
+    config::Config, constants::PREDICT_VERIFY_MAX_FRAMES, save::Save, stats::CornerHit,
+    world::Boundary,
+};
+
+// when an entity hits a corner of the world next
+#[derive(Clone, PartialEq, Serialize, Debug)]
+pub enum CornerPrediction {
+    Hit(CornerHit),
+    // the entity never bounces off two edges in the same frame
+    Never,
+    // the bounces of the entity can not be computed, with the reason why
+    Unpredictable(String),
+}
+
+// one axis of an entity moving between two world edges, like Entity::bounce()
+#[derive(Clone, Copy, Debug)]
+pub struct AxisMotion {
+    // the left or top edge of the entity
+    position: i64,
+    // the largest position that keeps the entity inside the world
+    max_position: i64,
+    velocity: i64,
+}
+
+// the bounces of one axis, after the first bounce the axis bounces every period frames,
+// alternating between the near (left or top) and far (right or bottom) edge
+#[derive(Clone, Copy, Debug)]
+struct AxisBounces {
+    first_frame: u64,
+    is_far_edge: bool,
+    period: Option<u64>,
+}
+
+// the predicted next corner hits of a world save, used by the predict command
+#[derive(Serialize, Debug)]
+pub struct SavePrediction {
+    pub path: PathBuf,
+    pub world_width: i32,
+    pub world_height: i32,
+    pub entities: Vec<EntityPrediction>,
+    // frames stepped to check the predictions, zero when they were not checked
+    pub verified_frames: u64,
+}
+
+#[derive(Serialize, Debug)]
+pub struct EntityPrediction {
+    pub index: usize,
+    pub entity_type: String,
+    pub prediction: CornerPrediction,
+    // whether stepping the world gave the same first corner hit, none when not checked
+    pub verified: Option<bool>,
+}
+
+impl AxisMotion {
+    pub fn new(position: i32, size: i32, world_size: i32, velocity: i32) -> AxisMotion {
+        AxisMotion {
+            position: position as i64,
+            max_position: (world_size - size) as i64,
+            velocity: velocity as i64,
+        }
+    }
+
+    // move one frame, returns the edge the axis bounced off (true for the far edge), if any
+    fn step(&mut self) -> Option<bool> {
+        self.position += self.velocity;
+        if self.position < 0 {
+            self.position = 0;
+            self.velocity = self.velocity.abs();
+            Some(false)
+        } else if self.position > self.max_position {
+            self.position = self.max_position;
+            self.velocity = -self.velocity.abs();
+            Some(true)
+        } else {
+            None
+        }
+    }
+
+    // the bounces of the axis in frames from now, none when the axis never bounces
+    fn bounces(mut self) -> Option<AxisBounces> {
+        let speed = self.velocity.unsigned_abs();
+        let period = (speed > 0).then(|| self.max_position as u64 / speed + 1);
+
+        // the first frame can start outside the world, after it the entity stays inside
+        if let Some(is_far_edge) = self.step() {
+            return Some(AxisBounces {
+                first_frame: 1,
+                is_far_edge,
+                period,
+            });
+        }
+        if speed == 0 {
+            return None;
+        }
+
+        let is_far_edge = self.velocity > 0;
+        let distance = if is_far_edge {
+            self.max_position - self.position
+        } else {
+            self.position
+        };
+        Some(AxisBounces {
+            first_frame: 1 + distance as u64 / speed + 1,
+            is_far_edge,
+            period,
+        })
+    }
+}
+
+impl AxisBounces {
+    // the edge of the bounce in the given frame, which must be a bounce of this axis
+    fn is_far_edge_at(&self, frame: u64) -> bool {
+        let bounce_count = match self.period {
+            Some(period) => (frame - self.first_frame) / period,
+            None => 0,
+        };
+        self.is_far_edge != (bounce_count % 2 == 1)
+    }
+
+    fn is_bounce_at(&self, frame: u64) -> bool {
+        match self.period {
+            _ if frame < self.first_frame => false,
+            Some(period) => (frame - self.first_frame).is_multiple_of(period),
+            None => frame == self.first_frame,
+        }
+    }
+}
+
+// the first frame both axes bounce in, and whether they bounce off their far edges (right and bottom)
+pub fn next_common_bounce(x_motion: AxisMotion, y_motion: AxisMotion) -> Option<(u64, bool, bool)> {
+    let x_bounces = x_motion.bounces()?;
+    let y_bounces = y_motion.bounces()?;
+
+    let frame = match (x_bounces.period, y_bounces.period) {
+        (Some(x_period), Some(y_period)) => first_common_frame(
+            x_bounces.first_frame,
+            x_period,
+            y_bounces.first_frame,
+            y_period,
+        )?,
+        // an axis that bounces once can only hit a corner in that frame
+        (None, _) => Some(x_bounces.first_frame).filter(|frame| y_bounces.is_bounce_at(*frame))?,
+        (_, None) => Some(y_bounces.first_frame).filter(|frame| x_bounces.is_bounce_at(*frame))?,
+    };
+    Some((
+        frame,
+        x_bounces.is_far_edge_at(frame),
+        y_bounces.is_far_edge_at(frame),
+    ))
+}
+
+// the first frame that is both x_first + a * x_period and y_first + b * y_period (chinese remainder theorem)
+fn first_common_frame(x_first: u64, x_period: u64, y_first: u64, y_period: u64) -> Option<u64> {
+    let (x_first, x_period) = (x_first as i128, x_period as i128);
+    let (y_first, y_period) = (y_first as i128, y_period as i128);
+
+    // x_period * x_factor + y_period * y_factor = divisor
+    let (divisor, x_factor, _) = extended_gcd(x_period, y_period);
+    if (y_first - x_first) % divisor != 0 {
+        return None;
+    }
+
+    let y_step = y_period / divisor;
+    let common_period = x_period * y_step;
+    let steps = ((y_first - x_first) / divisor * x_factor).rem_euclid(y_step);
+    let frame = x_first + x_period * steps;
+
+    // the same frame in every common period, the first one after both axes started bouncing
+    let first = x_first.max(y_first);
+    let frame = if frame < first {
+        frame + (first - frame + common_period - 1) / common_period * common_period
+    } else {
+        frame
+    };
+    u64::try_from(frame).ok()
+}
+
+// (gcd, x, y) with a * x + b * y = gcd
+fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    if b == 0 {
+        (a, 1, 0)
+    } else {
+        let (divisor, x, y) = extended_gcd(b, a % b);
+        (divisor, y, x - a / b * y)
+    }
+}
+
+impl SavePrediction {
+    pub fn new(save_path: &Path, world_save: &Save) -> SavePrediction {
+        let world = &world_save.world;
+        let (x_boundary, y_boundary) = world.boundaries();
+        // static entities change the path of the entities bouncing off the world edges
+        let has_obstacles = world.entities.iter().any(|entity| entity.is_static)
+            && x_boundary == Boundary::Bounce
+            && y_boundary == Boundary::Bounce;
+        let entities = world
+            .entities
+            .iter()
+            .enumerate()
+            .map(|(index, entity)| EntityPrediction {
+                index,
+                entity_type: entity.entity_type.name().to_string(),
+                prediction: if has_obstacles && !entity.is_static {
+                    CornerPrediction::Unpredictable(
+                        "the entity can bounce off static entities".to_string(),
+                    )
+                } else {
+                    entity.next_corner_hit(world.width, world.height, x_boundary, y_boundary)
+                },
+                verified: None,
+            })
+            .collect();
+
+        SavePrediction {
+            path: save_path.to_path_buf(),
+            world_width: world.width,
+            world_height: world.height,
+            entities,
+            verified_frames: 0,
+        }
+    }
+
+    // step the world frame by frame and compare its first corner hits with the predictions,
+    // predictions past the frame limit are not checked
+    pub fn verify(&mut self, mut world_save: Save) {
+        let last_frame = self
+            .entities
+            .iter()
+            .map(|entity| match &entity.prediction {
+                CornerPrediction::Hit(corner_hit) => corner_hit.frame,
+                CornerPrediction::Never => PREDICT_VERIFY_MAX_FRAMES,
+                CornerPrediction::Unpredictable(_) => 0,
+            })
+            .max()
+            .unwrap_or(0)
+            .min(PREDICT_VERIFY_MAX_FRAMES);
+
+        // entities are matched by their index, as despawned entities are removed from the world
+        for (index, entity) in world_save.world.entities.iter_mut().enumerate() {
+            entity.id = Some(index.to_string());
+        }
+        let config = Config::reverse_from_world_save(&world_save);
+        for _ in 0..last_frame {
+            world_save.world.update(&config, Duration::ZERO);
+        }
+
+        for entity in &mut self.entities {
+            // despawned entities did not hit a corner, only bouncing entities can
+            let first_corner_hit = world_save
+                .world
+                .entities
+                .iter()
+                .find(|world_entity| world_entity.id == Some(entity.index.to_string()))
+                .and_then(|world_entity| world_entity.stats.first_corner_hit);
+            entity.verified = match &entity.prediction {
+                CornerPrediction::Hit(corner_hit) if corner_hit.frame <= last_frame => {
+                    Some(first_corner_hit == Some(*corner_hit))
+                }
+                CornerPrediction::Never => Some(first_corner_hit.is_none()),
+                _ => None,
+            };
+        }
+        self.verified_frames = last_frame;
+    }
+
+    pub fn print(&self) {
+        println!(
+            "next corner hits in {} ({}x{}):",
+            self.path.display(),
+            self.world_width,
+            self.world_height
+        );
+        for entity in &self.entities {
+            let prediction = match &entity.prediction {
+                CornerPrediction::Hit(corner_hit) => {
+                    format!("frame {} ({} corner)", corner_hit.frame, corner_hit.corner)
+                }
+                CornerPrediction::Never => "never".to_string(),
+                CornerPrediction::Unpredictable(reason) => {
+                    format!("can not be predicted, {}", reason)
+                }
+            };
+            let verified = match entity.verified {
+                Some(true) => ", verified",
+                Some(false) => ", DOES NOT MATCH stepping the world",
+                None if self.verified_frames > 0 => ", not verified",
+                None => "",
+            };
+            println!(
+                "  #{} {}: {}{}",
+                entity.index, entity.entity_type, prediction, verified
+            );
+        }
+        if self.verified_frames > 0 {
+            println!("stepped the world {} frames", self.verified_frames);
+        }
+    }
+
+    pub fn is_verified(&self) -> bool {
+        self.entities
+            .iter()
+            .all(|entity| entity.verified != Some(false))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::EntityGroup,
+        entity::{Entity, EntityType},
+        stats::CornerHit,
+        world::World,
+    };
+
+    const WORLD_WIDTH: i32 = 31;
+    const WORLD_HEIGHT: i32 = 23;
+    const MAX_FRAMES: u64 = 2000;
+
+    // the first corner hit of the entity alone in a world, found by stepping the world
+    fn stepped_corner_hit(entity: Entity) -> Option<CornerHit> {
+        let mut config = Config::new();
+        config.world_width = WORLD_WIDTH;
+        config.world_height = WORLD_HEIGHT;
+        config.entity_type = EntityType::Box(1, 1);
+        let mut world = World::new(&config).expect("a 1x1 box fits the world");
+        world.entities = vec![entity];
+
+        for _ in 0..MAX_FRAMES {
+            world.update(&config, Duration::ZERO);
+            if let Some(corner_hit) = world.entities[0].stats.first_corner_hit {
+                return Some(corner_hit);
+            }
+        }
+        None
+    }
+
+    #[test]
+    fn predictions_match_stepping_the_world() {
+        let entity_types = [
+            EntityType::Box(1, 1),
+            EntityType::Box(4, 7),
+            EntityType::Ball(3),
+            // as wide as the world, bouncing off both side edges every frame it moves sideways
+            EntityType::Box(WORLD_WIDTH, 5),
+        ];
+        // the last positions start partly outside the world
+        let positions = [
+            (0, 0),
+            (5, 9),
+            (12, 3),
+            (-3, 4),
+            (WORLD_WIDTH - 2, WORLD_HEIGHT - 1),
+        ];
+
+        for entity_type in &entity_types {
+            for &(x_position, y_position) in &positions {
+                for x_velocity in -7..=7 {
+                    for y_velocity in -7..=7 {
+                        let mut entity = Entity::new(&EntityGroup {
+                            entity_type: entity_type.clone(),
+                            ..EntityGroup::new()
+                        });
+                        entity.x_position = x_position;
+                        entity.y_position = y_position;
+                        entity.x_velocity = x_velocity;
+                        entity.y_velocity = y_velocity;
+
+                        let prediction = entity.next_corner_hit(
+                            WORLD_WIDTH,
+                            WORLD_HEIGHT,
+                            Boundary::Bounce,
+                            Boundary::Bounce,
+                        );
+                        let stepped = stepped_corner_hit(entity);
+                        let matches = match &prediction {
+                            CornerPrediction::Hit(corner_hit) if corner_hit.frame <= MAX_FRAMES => {
+                                stepped == Some(*corner_hit)
+                            }
+                            CornerPrediction::Hit(_) | CornerPrediction::Never => stepped.is_none(),
+                            CornerPrediction::Unpredictable(_) => false,
+                        };
+                        assert!(
+                            matches,
+                            "{:?} at {:?} moving {:?}: predicted {:?}, stepped {:?}",
+                            entity_type,
+                            (x_position, y_position),
+                            (x_velocity, y_velocity),
+                            prediction,
+                            stepped
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use rand::{
+    distributions::{uniform::SampleUniform, WeightedIndex},
+    prelude::Distribution,
+    Rng,
+};
+use serde_derive::{Deserialize, Serialize};
+
+// a value picked from a list of choices, e.g. "ff0000ff" or ["ff0000ff", "0000ffff"]
+#[derive(Clone, PartialEq, Deserialize, Serialize, Debug)]
+#[serde(untagged)]
+pub enum RandomChoice<T> {
+    Value(T),
+    Choices(Vec<Choice<T>>),
+}
+
+// a number picked from a range or a list of choices, e.g. 5, { min: 2, max: 8 } or [2, 4, 8]
+#[derive(Clone, PartialEq, Deserialize, Serialize, Debug)]
+#[serde(untagged)]
+pub enum RandomNumber<T> {
+    Value(T),
+    // min and max are both included
+    Range { min: T, max: T },
+    Choices(Vec<Choice<T>>),
+}
+
+// a choice in a list, weighted choices are picked more often, e.g. { value: 2, weight: 3 }
+#[derive(Clone, PartialEq, Deserialize, Serialize, Debug)]
+#[serde(untagged)]
+pub enum Choice<T> {
+    Weighted { value: T, weight: u32 },
+    Value(T),
+}
+
+impl<T: Clone> RandomChoice<T> {
+    // lists of choices need at least one choice with a weight above 0 to pick from
+    pub fn is_valid(&self) -> bool {
+        match self {
+            RandomChoice::Value(_) => true,
+            RandomChoice::Choices(choices) => has_weight(choices),
+        }
+    }
+
+    pub fn pick(&self) -> T {
+        match self {
+            RandomChoice::Value(value) => value.clone(),
+            RandomChoice::Choices(choices) => pick_choice(choices),
+        }
+    }
+}
+
+impl<T: Clone + PartialOrd + SampleUniform> RandomNumber<T> {
+    // lists of choices need at least one choice with a weight above 0 to pick from
+    pub fn is_valid(&self) -> bool {
+        match self {
+            RandomNumber::Value(_) | RandomNumber::Range { .. } => true,
+            RandomNumber::Choices(choices) => has_weight(choices),
+        }
+    }
+
+    pub fn pick(&self) -> T {
+        match self {
+            RandomNumber::Value(value) => value.clone(),
+            RandomNumber::Range { min, max } if min < max => {
+                rand::thread_rng().gen_range(min.clone()..=max.clone())
+            }
+            RandomNumber::Range { min, .. } => min.clone(),
+            RandomNumber::Choices(choices) => pick_choice(choices),
+        }
+    }
+
+    // the largest value that can be picked, none for a list of choices without a weight above 0
+    pub fn largest(&self) -> Option<T> {
+        match self {
+            RandomNumber::Value(value) => Some(value.clone()),
+            RandomNumber::Range { min, max } if min < max => Some(max.clone()),
+            RandomNumber::Range { min, .. } => Some(min.clone()),
+            RandomNumber::Choices(choices) => choices
+                .iter()
+                .filter(|choice| choice.weight() > 0)
+                .map(Choice::value)
+                .fold(None, |largest: Option<&T>, value| match largest {
+                    Some(largest) if *largest >= *value => Some(largest),
+                    _ => Some(value),
+                })
+                .cloned(),
+        }
+    }
+}
+
+impl<T> Choice<T> {
+    fn value(&self) -> &T {
+        match self {
+            Choice::Weighted { value, .. } | Choice::Value(value) => value,
+        }
+    }
+
+    fn weight(&self) -> u32 {
+        match self {
+            Choice::Weighted { weight, .. } => *weight,
+            Choice::Value(_) => 1,
+        }
+    }
+}
+
+fn has_weight<T>(choices: &[Choice<T>]) -> bool {
+    choices.iter().any(|choice| choice.weight() > 0)
+}
+
+fn pick_choice<T: Clone>(choices: &[Choice<T>]) -> T {
+    let weighted_index = WeightedIndex::new(choices.iter().map(Choice::weight))
+        .expect("choices must not be empty and need at least one weight above 0");
+    choices[weighted_index.sample(&mut rand::thread_rng())]
+        .value()
+        .clone()
+}
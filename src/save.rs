@@ -1,10 +1,18 @@
-use std::{fs, path::Path};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
 
+use chrono::SecondsFormat;
+use image::{imageops, RgbaImage};
 use serde_derive::{Deserialize, Serialize};
 
 use crate::{
     config::Config,
-    constants::{WORLD_SAVE_FILE_EXTENSION, WORLD_SAVE_LOCATION, YAML_CONFIG_PATH},
+    constants::{
+        LEGACY_FORMAT_VERSION, SAVE_FORMAT_VERSION, WORLD_SAVE_FILE_EXTENSION,
+        WORLD_SAVE_THUMBNAIL_PATH, WORLD_SAVE_THUMBNAIL_SIZE, YAML_CONFIG_PATH,
+    },
     world::World,
 };
 
@@ -13,51 +21,201 @@ pub struct Save {
     pub world: World,
     pub date_time_stamp: String,
     pub bouncy_world_engine_version: String,
+    // the version of the world save format, older versions are read with defaults for the fields added since
+    #[serde(default = "legacy_format_version")]
+    pub format_version: u32,
+
+    // optional metadata, used to browse a library of world saves
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    // rfc 3339 date-times with timezone, e.g. 2024-01-31T13:45:00-05:00
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified_at: Option<String>,
+    // path of the thumbnail image, relative to the world save
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thumbnail: Option<String>,
 }
 
 impl Save {
     pub fn new(world: World) -> Save {
+        let now = chrono::offset::Local::now();
         Save {
             world,
             // TODO: add am/pm in format
-            date_time_stamp: chrono::offset::Local::now()
-                .format("%Y-%m-%dT%H.%M.%S")
-                .to_string(),
+            date_time_stamp: now.format("%Y-%m-%dT%H.%M.%S").to_string(),
             bouncy_world_engine_version: env!("CARGO_PKG_VERSION").to_string(),
+            format_version: SAVE_FORMAT_VERSION,
+            title: None,
+            author: None,
+            description: None,
+            tags: vec![],
+            created_at: Some(now.to_rfc3339_opts(SecondsFormat::Secs, false)),
+            modified_at: Some(now.to_rfc3339_opts(SecondsFormat::Secs, false)),
+            thumbnail: None,
         }
     }
 
-    pub fn load(save_path: &Path) -> Option<Save> {
-        // TODO: ability to load config via json?
-        if save_path.exists() {
-            let save_str = fs::read_to_string(save_path).expect("could not read from file");
-            Some(serde_yaml::from_str::<Save>(&save_str).expect("could not read as a save file"))
-        } else {
-            None
+    pub fn try_load(save_path: &Path) -> Result<Save, String> {
+        let save_str = fs::read_to_string(save_path)
+            .map_err(|error| format!("could not read from file {:?}: {}", save_path, error))?;
+        let save = serde_yaml::from_str::<Save>(&save_str)
+            .map_err(|error| format!("could not read {:?} as a save file: {}", save_path, error))?;
+        if save.format_version > SAVE_FORMAT_VERSION {
+            return Err(format!(
+                "{:?} has world save format version {}, this engine reads up to version {}",
+                save_path, save.format_version, SAVE_FORMAT_VERSION
+            ));
         }
+        // emitters create entities while the world runs, so they are checked before it runs
+        save.world
+            .check_emitters()
+            .map_err(|error| format!("invalid world save {:?}: {}", save_path, error))?;
+        Ok(save)
+    }
+
+    // find the world save files in the given save location, each save is in its own directory
+    pub fn find_save_files(save_location: &Path) -> Vec<PathBuf> {
+        let Ok(save_dirs) = fs::read_dir(save_location) else {
+            return vec![];
+        };
+
+        let mut save_paths: Vec<PathBuf> = save_dirs
+            .filter_map(|save_dir| save_dir.ok())
+            .filter(|save_dir| save_dir.path().is_dir())
+            .filter_map(|save_dir| fs::read_dir(save_dir.path()).ok())
+            .flatten()
+            .filter_map(|save_file| save_file.ok().map(|save_file| save_file.path()))
+            .filter(|save_path| {
+                save_path
+                    .extension()
+                    .is_some_and(|extension| extension == WORLD_SAVE_FILE_EXTENSION)
+            })
+            .collect();
+        save_paths.sort();
+        save_paths
     }
 
-    pub fn save(&self, config: &Config) -> String {
-        fs::create_dir_all(WORLD_SAVE_LOCATION)
-            .expect("could not create dir at WORLD_SAVE_LOCATION");
+    // save the world, its config and a thumbnail to a new directory in the given save location
+    pub fn save(&mut self, config: &Config, save_location: &Path) -> Result<String, String> {
+        let save_path = save_location.join(&self.date_time_stamp);
+        fs::create_dir_all(&save_path)
+            .map_err(|error| format!("could not create directory {:?}: {}", save_path, error))?;
 
-        let save_path = &format!("{}\\{}", WORLD_SAVE_LOCATION, &self.date_time_stamp);
-        fs::create_dir_all(save_path).expect("could not create dir at self.date_time_stamp");
+        self.modified_at =
+            Some(chrono::offset::Local::now().to_rfc3339_opts(SecondsFormat::Secs, false));
+        // world saves of older versions are written in the current format
+        self.format_version = SAVE_FORMAT_VERSION;
 
-        let save_str = serde_yaml::to_string(&self).expect("could not write config as yaml");
+        // a missing thumbnail should not stop the world from being saved
+        let thumbnail_path = save_path.join(WORLD_SAVE_THUMBNAIL_PATH);
+        self.thumbnail = match self.render_thumbnail().and_then(|thumbnail| {
+            thumbnail
+                .save(&thumbnail_path)
+                .map_err(|error| error.to_string())
+        }) {
+            Ok(()) => Some(WORLD_SAVE_THUMBNAIL_PATH.to_string()),
+            Err(error) => {
+                eprintln!("warning: could not save thumbnail: {}", error);
+                None
+            }
+        };
 
-        std::fs::write(
-            format!(
-                "{}\\{}.{}",
-                save_path, &self.date_time_stamp, WORLD_SAVE_FILE_EXTENSION
-            ),
-            save_str,
-        )
-        .expect("could not write to file");
+        let save_str = serde_yaml::to_string(&self)
+            .map_err(|error| format!("could not write world save as yaml: {}", error))?;
+        let save_file_path = save_path.join(format!(
+            "{}.{}",
+            &self.date_time_stamp, WORLD_SAVE_FILE_EXTENSION
+        ));
+        fs::write(&save_file_path, save_str)
+            .map_err(|error| format!("could not write to file {:?}: {}", save_file_path, error))?;
+
+        let config_file_location = save_path.join(YAML_CONFIG_PATH);
+        config.save_to_yaml_file(
+            config_file_location
+                .to_str()
+                .ok_or(format!("{:?} is not a valid path", config_file_location))?,
+        )?;
+
+        Ok(save_path.display().to_string())
+    }
+
+    // draw the first frame of the world, scaled down to fit the thumbnail size
+    pub fn render_thumbnail(&self) -> Result<RgbaImage, String> {
+        if self.world.width <= 0 || self.world.height <= 0 {
+            return Err(format!(
+                "world is {}x{}",
+                self.world.width, self.world.height
+            ));
+        }
 
-        let config_file_location = format!("{}\\{}", save_path, YAML_CONFIG_PATH);
-        config.save_to_yaml_file(&config_file_location);
+        let images = self.world.load_images()?;
+        let mut frame = vec![0; self.world.width as usize * self.world.height as usize * 4];
+        self.world.draw(&mut frame, &images);
+
+        let frame = RgbaImage::from_raw(self.world.width as u32, self.world.height as u32, frame)
+            .ok_or("frame does not match the world size")?;
+        let scale = (WORLD_SAVE_THUMBNAIL_SIZE as f64
+            / self.world.width.max(self.world.height) as f64)
+            .min(1.0);
+        Ok(imageops::thumbnail(
+            &frame,
+            ((self.world.width as f64 * scale) as u32).max(1),
+            ((self.world.height as f64 * scale) as u32).max(1),
+        ))
+    }
+}
+
+pub fn legacy_format_version() -> u32 {
+    LEGACY_FORMAT_VERSION
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::EntityType;
+
+    // write a world save with the given format version, or without one, and load it again
+    fn load_with_format_version(format_version: Option<u32>) -> Result<Save, String> {
+        let mut config = Config::new();
+        config.entity_type = EntityType::Box(10, 10);
+        let save = Save::new(World::new(&config).expect("boxes fit the world"));
+        let format_version_line = match format_version {
+            Some(format_version) => format!("format_version: {}\n", format_version),
+            None => String::new(),
+        };
+        let save_str = serde_yaml::to_string(&save)
+            .expect("world saves serialize")
+            .replace(
+                &format!("format_version: {}\n", SAVE_FORMAT_VERSION),
+                &format_version_line,
+            );
+        let save_path = std::env::temp_dir().join(format!(
+            "bouncy-world-format-{}-{:?}.bouncy-world",
+            std::process::id(),
+            format_version
+        ));
+        fs::write(&save_path, save_str).expect("could not write the world save");
+
+        let result = Save::try_load(&save_path);
+        fs::remove_file(&save_path).expect("could not remove the world save");
+        result
+    }
 
-        save_path.to_string()
+    #[test]
+    fn world_saves_are_checked_for_their_format_version() {
+        let save = load_with_format_version(None).expect("saves without a version are read");
+        assert_eq!(save.format_version, LEGACY_FORMAT_VERSION);
+        let save = load_with_format_version(Some(SAVE_FORMAT_VERSION))
+            .expect("saves of the current version are read");
+        assert_eq!(save.format_version, SAVE_FORMAT_VERSION);
+        assert!(load_with_format_version(Some(SAVE_FORMAT_VERSION + 1)).is_err());
     }
 }
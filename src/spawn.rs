@@ -0,0 +1,327 @@
+use std::{f64::consts::PI, fmt};
+
+use rand::Rng;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::entity::{Entity, EntityType};
+
+// where entities are placed when a world is created,
+// positions are entity positions as in world saves (the top left corner, or the center pixel of a Ball)
+#[derive(Clone, PartialEq, Deserialize, Serialize, Debug)]
+pub enum Spawn {
+    // a random position anywhere in the world
+    Random,
+    // a random position inside a rectangle
+    Region {
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+    },
+    // fixed positions, one per entity, repeated when there are more entities than positions
+    Positions(Vec<(i32, i32)>),
+    // rows of entities starting at x and y, spacing is the distance between entity positions
+    Grid {
+        x: i32,
+        y: i32,
+        columns: i32,
+        spacing: i32,
+    },
+    // entities spread evenly on a circle around x and y
+    Circle {
+        x: i32,
+        y: i32,
+        radius: i32,
+    },
+}
+
+// what to do with entities that do not fit the world (or their spawn region)
+#[derive(Clone, Copy, Eq, PartialEq, Deserialize, Serialize, Debug)]
+pub enum EntityFit {
+    // stop creating the world with an error
+    Error,
+    // keep the entity size, place the entity in the top left corner, entities larger than the world are shrunk
+    Clamp,
+    // shrink the entity until it fits, images are cropped
+    Shrink,
+}
+
+// why a world could not be created
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum SpawnError {
+    // the world width or height is zero or negative
+    InvalidWorldSize {
+        width: i32,
+        height: i32,
+    },
+    // the spawn region width or height is zero or negative
+    InvalidRegionSize {
+        width: i32,
+        height: i32,
+    },
+    // an entity width, height or radius is zero or negative, or a polygon has less than 3 sides
+    InvalidEntitySize {
+        entity_type: EntityType,
+    },
+    // a list of choices is empty or all its weights are 0, so nothing can be picked
+    InvalidChoices {
+        field_name: &'static str,
+    },
+    // an entity is larger than the world or its spawn region
+    EntityTooLarge {
+        entity_type: EntityType,
+        area: &'static str,
+        width: i32,
+        height: i32,
+    },
+}
+
+impl fmt::Display for SpawnError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SpawnError::InvalidWorldSize { width, height } => {
+                write!(
+                    f,
+                    "the world size must be positive, got {}x{}",
+                    width, height
+                )
+            }
+            SpawnError::InvalidRegionSize { width, height } => write!(
+                f,
+                "the spawn region size must be positive, got {}x{}",
+                width, height
+            ),
+            SpawnError::InvalidEntitySize { entity_type } => {
+                write!(f, "the entity size must be positive, got {:?}", entity_type)
+            }
+            SpawnError::InvalidChoices { field_name } => write!(
+                f,
+                "the entity {} choices are empty or all have a weight of 0, at least one choice needs a weight above 0",
+                field_name
+            ),
+            SpawnError::EntityTooLarge {
+                entity_type,
+                area,
+                width,
+                height,
+            } => {
+                let (entity_width, entity_height) = entity_type.size();
+                write!(
+                    f,
+                    "{:?} ({}x{}) does not fit the {} ({}x{}), set entity_fit to Clamp or Shrink to create the world anyway",
+                    entity_type, entity_width, entity_height, area, width, height
+                )
+            }
+        }
+    }
+}
+
+impl Spawn {
+    // the position of the entity at the given index of its group
+    pub fn position(
+        &self,
+        entity: &Entity,
+        index: i32,
+        count: i32,
+        world_width: i32,
+        world_height: i32,
+    ) -> (i32, i32) {
+        match self {
+            Spawn::Random => random_position_within(entity, 0, 0, world_width, world_height),
+            Spawn::Region {
+                x,
+                y,
+                width,
+                height,
+            } => random_position_within(entity, *x, *y, *width, *height),
+            Spawn::Positions(positions) if positions.is_empty() => {
+                random_position_within(entity, 0, 0, world_width, world_height)
+            }
+            Spawn::Positions(positions) => positions[index as usize % positions.len()],
+            Spawn::Grid {
+                x,
+                y,
+                columns,
+                spacing,
+            } => {
+                let columns = (*columns).max(1);
+                (x + index % columns * spacing, y + index / columns * spacing)
+            }
+            Spawn::Circle { x, y, radius } => {
+                let angle = 2.0 * PI * index as f64 / count.max(1) as f64;
+                (
+                    x + (*radius as f64 * angle.cos()).round() as i32,
+                    y + (*radius as f64 * angle.sin()).round() as i32,
+                )
+            }
+        }
+    }
+
+    // the size of the area entities are spawned in, checked before placing entities
+    pub fn area_size(&self, world_width: i32, world_height: i32) -> Result<(i32, i32), SpawnError> {
+        match self {
+            Spawn::Region { width, height, .. } if *width <= 0 || *height <= 0 => {
+                Err(SpawnError::InvalidRegionSize {
+                    width: *width,
+                    height: *height,
+                })
+            }
+            Spawn::Region { width, height, .. } => Ok((*width, *height)),
+            _ => Ok((world_width, world_height)),
+        }
+    }
+
+    // the same spawn with its positions moved, sizes and spacing are kept
+    pub fn moved(&self, move_position: impl Fn((i32, i32)) -> (i32, i32)) -> Spawn {
+        match self {
+            Spawn::Random => Spawn::Random,
+            Spawn::Region {
+                x,
+                y,
+                width,
+                height,
+            } => {
+                let (x, y) = move_position((*x, *y));
+                Spawn::Region {
+                    x,
+                    y,
+                    width: *width,
+                    height: *height,
+                }
+            }
+            Spawn::Positions(positions) => Spawn::Positions(
+                positions
+                    .iter()
+                    .map(|position| move_position(*position))
+                    .collect(),
+            ),
+            Spawn::Grid {
+                x,
+                y,
+                columns,
+                spacing,
+            } => {
+                let (x, y) = move_position((*x, *y));
+                Spawn::Grid {
+                    x,
+                    y,
+                    columns: *columns,
+                    spacing: *spacing,
+                }
+            }
+            Spawn::Circle { x, y, radius } => {
+                let (x, y) = move_position((*x, *y));
+                Spawn::Circle {
+                    x,
+                    y,
+                    radius: *radius,
+                }
+            }
+        }
+    }
+
+    // only random positions can be picked again to avoid overlapping entities
+    pub fn is_random(&self) -> bool {
+        match self {
+            Spawn::Random | Spawn::Region { .. } => true,
+            Spawn::Positions(positions) => positions.is_empty(),
+            Spawn::Grid { .. } | Spawn::Circle { .. } => false,
+        }
+    }
+}
+
+// a random position that keeps the whole entity inside the rectangle
+fn random_position_within(entity: &Entity, x: i32, y: i32, width: i32, height: i32) -> (i32, i32) {
+    // the distance from the entity position to the edges of the entity
+    let (left, top, right, bottom) = entity.bounds();
+    let (left, top) = (entity.x_position - left, entity.y_position - top);
+    let (right, bottom) = (right - entity.x_position, bottom - entity.y_position);
+
+    let mut random = rand::thread_rng();
+    let min_x_position = x + left;
+    let max_x_position = (x + width - right).max(min_x_position);
+    let min_y_position = y + top;
+    let max_y_position = (y + height - bottom).max(min_y_position);
+    (
+        random.gen_range(min_x_position..=max_x_position),
+        random.gen_range(min_y_position..=max_y_position),
+    )
+}
+
+// check that the entity type fits the spawn area, shrinking it when allowed
+pub fn fit_entity_type(
+    entity_type: &EntityType,
+    spawn: &Spawn,
+    entity_fit: EntityFit,
+    world_width: i32,
+    world_height: i32,
+) -> Result<EntityType, SpawnError> {
+    let (width, height) = spawn.area_size(world_width, world_height)?;
+    let (entity_width, entity_height) = entity_type.size();
+    if entity_width <= width && entity_height <= height {
+        return Ok(entity_type.clone());
+    }
+
+    match entity_fit {
+        EntityFit::Error => Err(SpawnError::EntityTooLarge {
+            entity_type: entity_type.clone(),
+            area: if matches!(spawn, Spawn::Region { .. }) {
+                "spawn region"
+            } else {
+                "world"
+            },
+            width,
+            height,
+        }),
+        // entities larger than the world would touch both edges and bounce every frame, they are shrunk instead
+        EntityFit::Clamp if entity_width > world_width || entity_height > world_height => {
+            Ok(shrink_entity_type(entity_type, world_width, world_height))
+        }
+        EntityFit::Clamp => Ok(entity_type.clone()),
+        EntityFit::Shrink => Ok(shrink_entity_type(entity_type, width, height)),
+    }
+}
+
+// shrink the entity until it fits within width and height
+fn shrink_entity_type(entity_type: &EntityType, width: i32, height: i32) -> EntityType {
+    let (entity_width, entity_height) = entity_type.size();
+    match entity_type {
+        EntityType::Box(entity_width, entity_height) => {
+            EntityType::Box((*entity_width).min(width), (*entity_height).min(height))
+        }
+        EntityType::Ball(radius) => {
+            // a ball is 2 * radius - 1 pixels wide
+            EntityType::Ball((*radius).min((width.min(height) + 1) / 2).max(1))
+        }
+        EntityType::Image(image_path, entity_width, entity_height) => EntityType::Image(
+            image_path.to_string(),
+            (*entity_width).min(width),
+            (*entity_height).min(height),
+        ),
+        EntityType::Text(entity_width, entity_height) => {
+            EntityType::Text((*entity_width).min(width), (*entity_height).min(height))
+        }
+        // sprites are cropped like images, frames are still found by their size in the sprite sheet
+        EntityType::Sprite {
+            path,
+            width: entity_width,
+            height: entity_height,
+            frame_rate,
+            loop_mode,
+            frame_size,
+        } => EntityType::Sprite {
+            path: path.to_string(),
+            width: (*entity_width).min(width),
+            height: (*entity_height).min(height),
+            frame_rate: *frame_rate,
+            loop_mode: *loop_mode,
+            frame_size: Some(frame_size.unwrap_or((*entity_width, *entity_height))),
+        },
+        // other shapes keep their proportions
+        EntityType::Ellipse(_, _)
+        | EntityType::RegularPolygon(_, _)
+        | EntityType::Polygon(_)
+        | EntityType::Line(_, _, _) => entity_type
+            .scaled((width as f64 / entity_width as f64).min(height as f64 / entity_height as f64)),
+    }
+}
@@ -0,0 +1,151 @@
+use std::fmt;
+
+use serde_derive::{Deserialize, Serialize};
+
+// a corner of the world
+#[derive(Clone, Copy, Eq, PartialEq, Deserialize, Serialize, Debug)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+// an entity bouncing off two world edges in the same frame
+#[derive(Clone, Copy, Eq, PartialEq, Serialize, Debug)]
+pub struct CornerHit {
+    // frames since the entity started moving, the first update is frame 1
+    pub frame: u64,
+    pub corner: Corner,
+}
+
+// what happened to an entity since the world started running
+#[derive(Clone, Default, PartialEq, Serialize, Debug)]
+pub struct EntityStats {
+    // bounces off a world edge, a corner hit counts as a bounce off both edges
+    pub bounces: u64,
+    pub corner_hits: u64,
+    // bounces off static entities
+    pub obstacle_bounces: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_corner_hit: Option<CornerHit>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_corner_hit: Option<CornerHit>,
+    // pixels moved since the world started running
+    pub distance: f64,
+    pub frames_alive: u64,
+}
+
+// the totals of the entities removed from a running world, counted in the world stats
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct DespawnedStats {
+    pub entities: u64,
+    pub bounces: u64,
+    pub corner_hits: u64,
+}
+
+// the stats of every entity in a running world
+#[derive(Serialize, Debug)]
+pub struct WorldStats {
+    pub frames: u64,
+    // bounces and corner hits of all entities, including the despawned ones
+    pub bounces: u64,
+    pub corner_hits: u64,
+    // entities removed by a despawning edge or their lifetime, their stats are not in entities
+    pub despawned: u64,
+    pub entities: Vec<EntityStatsSummary>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct EntityStatsSummary {
+    pub index: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub entity_type: String,
+    #[serde(flatten)]
+    pub stats: EntityStats,
+}
+
+impl Corner {
+    // the corner between the left or right edge and the top or bottom edge
+    pub fn new(is_right: bool, is_bottom: bool) -> Corner {
+        match (is_right, is_bottom) {
+            (false, false) => Corner::TopLeft,
+            (true, false) => Corner::TopRight,
+            (false, true) => Corner::BottomLeft,
+            (true, true) => Corner::BottomRight,
+        }
+    }
+}
+
+impl fmt::Display for Corner {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Corner::TopLeft => "top left",
+            Corner::TopRight => "top right",
+            Corner::BottomLeft => "bottom left",
+            Corner::BottomRight => "bottom right",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl EntityStats {
+    // count the bounces of the current frame
+    pub fn record_bounce(&mut self, is_x_bounce: bool, is_y_bounce: bool, corner: Corner) {
+        self.bounces += is_x_bounce as u64 + is_y_bounce as u64;
+        if is_x_bounce && is_y_bounce {
+            let corner_hit = CornerHit {
+                frame: self.frames_alive,
+                corner,
+            };
+            self.corner_hits += 1;
+            self.first_corner_hit.get_or_insert(corner_hit);
+            self.last_corner_hit = Some(corner_hit);
+        }
+    }
+
+    // the corner the entity hit in the current frame, if any
+    pub fn corner_hit_this_frame(&self) -> Option<Corner> {
+        self.last_corner_hit
+            .filter(|corner_hit| corner_hit.frame == self.frames_alive)
+            .map(|corner_hit| corner_hit.corner)
+    }
+}
+
+impl DespawnedStats {
+    pub fn record(&mut self, stats: &EntityStats) {
+        self.entities += 1;
+        self.bounces += stats.bounces;
+        self.corner_hits += stats.corner_hits;
+    }
+}
+
+impl WorldStats {
+    pub fn print(&self) {
+        println!(
+            "world stats after {} frames: {} bounces, {} corner hits, {} despawned entities",
+            self.frames, self.bounces, self.corner_hits, self.despawned
+        );
+        for entity in &self.entities {
+            let first_corner_hit = match &entity.stats.first_corner_hit {
+                Some(corner_hit) => format!(
+                    "first corner hit at frame {} ({})",
+                    corner_hit.frame, corner_hit.corner
+                ),
+                None => "no corner hits yet".to_string(),
+            };
+            println!(
+                "  #{} {}: {} bounces, {} corner hits, {} obstacle bounces, {} frames alive, moved {:.0} pixels, {}",
+                entity.index,
+                entity.entity_type,
+                entity.stats.bounces,
+                entity.stats.corner_hits,
+                entity.stats.obstacle_bounces,
+                entity.stats.frames_alive,
+                entity.stats.distance,
+                first_corner_hit
+            );
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use hex::FromHex;
+use serde_derive::{Deserialize, Serialize};
+
+// how an entity is drawn, images are always drawn as they are
+#[derive(Clone, Default, Eq, PartialEq, Hash, Deserialize, Serialize, Debug)]
+#[serde(default)]
+pub struct Style {
+    // outline color, the entity color is used when not given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stroke_rgba_hex: Option<String>,
+    // outline width in pixels, drawn inside the edge of the entity
+    #[serde(skip_serializing_if = "is_zero")]
+    pub stroke_width: i32,
+    // radius of the rounded corners of Box and Text entities
+    #[serde(skip_serializing_if = "is_zero")]
+    pub corner_radius: i32,
+    // only draw the outline, 1 pixel wide when no stroke width is given
+    #[serde(skip_serializing_if = "is_false")]
+    pub hollow: bool,
+    // fill the entity with a gradient starting at the entity color
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gradient: Option<Gradient>,
+}
+
+#[derive(Clone, Eq, PartialEq, Hash, Deserialize, Serialize, Debug)]
+pub enum Gradient {
+    // to the given color along the angle in degrees, 0 is left to right and 90 is top to bottom
+    Linear(String, i32),
+    // to the given color from the center to the edges
+    Radial(String),
+}
+
+// what an entity leaves behind as it moves
+#[derive(Clone, Copy, Default, Eq, PartialEq, Hash, Deserialize, Serialize, Debug)]
+pub enum Trail {
+    // nothing, or the trail of the world for entities
+    #[default]
+    Off,
+    // fading copies of the entity at its given number of last positions
+    Fade(usize),
+    // the entity paints the world background as it moves
+    Paint,
+}
+
+impl Style {
+    pub fn is_default(&self) -> bool {
+        *self == Style::default()
+    }
+
+    // the width of the outline, hollow entities always have an outline
+    pub fn outline_width(&self) -> i32 {
+        if self.hollow {
+            self.stroke_width.max(1)
+        } else {
+            self.stroke_width
+        }
+    }
+}
+
+impl Trail {
+    pub fn is_default(&self) -> bool {
+        *self == Trail::default()
+    }
+}
+
+// mix two rrggbbaa colors, amount goes from 0 (only from) to 1 (only to)
+pub fn mix_rgba_hex(from_rgba_hex: &str, to_rgba_hex: &str, amount: f64) -> String {
+    let from = <[u8; 4]>::from_hex(from_rgba_hex)
+        .expect("issue converting rgba_str to rgba u8 slice, expected format: rrggbbaa");
+    let to = <[u8; 4]>::from_hex(to_rgba_hex)
+        .expect("issue converting rgba_str to rgba u8 slice, expected format: rrggbbaa");
+    let amount = amount.clamp(0.0, 1.0);
+    let mixed: Vec<u8> = from
+        .iter()
+        .zip(to.iter())
+        .map(|(from, to)| (*from as f64 + (*to as f64 - *from as f64) * amount).round() as u8)
+        .collect();
+    hex::encode(mixed)
+}
+
+pub fn is_zero(value: &i32) -> bool {
+    *value == 0
+}
+
+pub fn is_false(value: &bool) -> bool {
+    !*value
+}
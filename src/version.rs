@@ -0,0 +1,58 @@
+use serde_derive::Serialize;
+
+use crate::constants::{CONFIG_FORMAT_VERSION, SAVE_FORMAT_VERSION};
+
+// information about this build of the engine, used to check compatibility of world saves and configs
+#[derive(Serialize, Debug)]
+pub struct VersionInfo {
+    pub bouncy_world_engine_version: String,
+    pub save_format_version: u32,
+    pub config_format_version: u32,
+    pub features: Vec<String>,
+    pub git_commit: String,
+}
+
+impl VersionInfo {
+    pub fn new() -> VersionInfo {
+        VersionInfo {
+            bouncy_world_engine_version: env!("CARGO_PKG_VERSION").to_string(),
+            save_format_version: SAVE_FORMAT_VERSION,
+            config_format_version: CONFIG_FORMAT_VERSION,
+            // features and git commit are recorded by build.rs
+            features: env!("BOUNCY_WORLD_FEATURES")
+                .split(',')
+                .filter(|feature| !feature.is_empty())
+                .map(str::to_string)
+                .collect(),
+            git_commit: env!("BOUNCY_WORLD_GIT_COMMIT").to_string(),
+        }
+    }
+
+    pub fn print(&self) {
+        println!(
+            "The Bouncy World Engine - v{}",
+            self.bouncy_world_engine_version
+        );
+        println!("save format version: {}", self.save_format_version);
+        println!("config format version: {}", self.config_format_version);
+        if self.features.is_empty() {
+            println!("features: none");
+        } else {
+            println!("features: {}", self.features.join(", "));
+        }
+        println!("git commit: {}", self.git_commit);
+    }
+
+    pub fn print_json(&self) {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&self).expect("could not write version info as json")
+        );
+    }
+}
+
+impl Default for VersionInfo {
+    fn default() -> VersionInfo {
+        VersionInfo::new()
+    }
+}
@@ -1,20 +1,52 @@
-use std::collections::HashMap;
+use std::{borrow::Cow, collections::HashMap, fs::File, io::BufReader, path::Path, time::Duration};
 
 use crate::{
-    config::Config,
-    entity::{Entity, EntityType},
+    config::{Config, EntityGroup},
+    constants::SPAWN_ATTEMPTS,
+    emitter::Emitter,
+    entity::{Entity, EntityShape, EntityType},
+    spawn::{self, SpawnError},
+    stats::{Corner, DespawnedStats, EntityStatsSummary, WorldStats},
+    style::{self, Trail},
 };
 use hex::FromHex;
-use image::{DynamicImage, GenericImageView};
+use image::{
+    codecs::gif::GifDecoder, imageops, AnimationDecoder, DynamicImage, GenericImageView,
+    ImageResult, RgbaImage,
+};
 use serde_derive::{Deserialize, Serialize};
 
-#[derive(Eq, PartialEq, Hash, Deserialize, Serialize, Debug)]
+#[derive(PartialEq, Deserialize, Serialize, Debug)]
 pub struct World {
     pub width: i32,
     pub height: i32,
     pub world_type: WorldType,
     pub background_rgba_hex: String,
+    // what every entity leaves behind as it moves, unless the entity has its own trail
+    #[serde(default, skip_serializing_if = "Trail::is_default")]
+    pub trail: Trail,
+    // what happens to entities at the world edges
+    #[serde(default, skip_serializing_if = "Boundary::is_default")]
+    pub boundary: Boundary,
+    // used instead of boundary for the left and right edges
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub x_boundary: Option<Boundary>,
+    // used instead of boundary for the top and bottom edges
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub y_boundary: Option<Boundary>,
+    // spawn entities over time while the world runs
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub emitters: Vec<Emitter>,
     pub entities: Vec<Entity>,
+    // frames since the world started running
+    #[serde(skip)]
+    pub frames: u64,
+    // the background painted by entities with a Paint trail, row by row, empty until painted
+    #[serde(skip)]
+    pub paint: Vec<Option<String>>,
+    // the stats of the entities removed while the world runs
+    #[serde(skip)]
+    pub despawned: DespawnedStats,
 }
 
 #[derive(Clone, Eq, PartialEq, Hash, Deserialize, Serialize, Debug)]
@@ -24,130 +56,858 @@ pub enum WorldType {
     Image(String, i32, i32),
 }
 
+// what happens to an entity that moves past a world edge
+#[derive(Clone, Copy, Default, Eq, PartialEq, Hash, Deserialize, Serialize, Debug)]
+pub enum Boundary {
+    // bounce off the edge
+    #[default]
+    Bounce,
+    // come back in at the opposite edge, an entity crossing an edge is drawn on both sides
+    Wrap,
+    // remove the entity once it is completely outside the world
+    Despawn,
+    // stop at the edge and keep sliding along it
+    Clamp,
+}
+
+// how merged entities are fitted into the merged world when the world sizes differ
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum SizeMismatch {
+    // keep entity positions as they are
+    Keep,
+    // scale entity positions by the ratio between the world sizes
+    Scale,
+    // move entities that are outside the merged world back inside it
+    Clamp,
+    // grow the merged world to fit the largest world
+    Grow,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct MergeOptions {
+    // index of the world to take the size, world type and background from
+    pub background_from: usize,
+    // added to the positions of the entities and emitters of every other world
+    pub offset: (i32, i32),
+    pub size_mismatch: SizeMismatch,
+}
+
+// the color of an image pixel, none outside the image, as images can be smaller than the size they are given
 fn get_hex_pixel_color_from_image(
     pixel_x_position: i32,
     pixel_y_position: i32,
     image_path: &String,
     images: &HashMap<String, DynamicImage>,
-) -> String {
-    let image = images
-        .get(image_path)
-        .expect("image_path not found in images");
-
-    let rgba = image
-        .get_pixel((pixel_x_position) as u32, (pixel_y_position) as u32)
-        .0;
+) -> Option<String> {
+    let image = images.get(image_path)?;
+    let (x_position, y_position) = (
+        u32::try_from(pixel_x_position).ok()?,
+        u32::try_from(pixel_y_position).ok()?,
+    );
+    if x_position >= image.width() || y_position >= image.height() {
+        return None;
+    }
+
+    let rgba = image.get_pixel(x_position, y_position).0;
     let [r, g, b, a] = rgba;
-    format!("{:02x}{:02x}{:02x}{:02x}", r, g, b, a)
+    Some(format!("{:02x}{:02x}{:02x}{:02x}", r, g, b, a))
+}
+
+// open an image file, the frames of a gif are stacked top to bottom into one sprite sheet
+fn open_image(image_path: &str) -> ImageResult<DynamicImage> {
+    let is_gif = Path::new(image_path)
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("gif"));
+    if !is_gif {
+        return image::open(image_path);
+    }
+
+    let decoder = GifDecoder::new(BufReader::new(File::open(image_path)?))?;
+    let frames = decoder.into_frames().collect_frames()?;
+    let (width, height) = frames
+        .first()
+        .map_or((1, 1), |frame| frame.buffer().dimensions());
+    let mut sprite_sheet = RgbaImage::new(width, height * frames.len().max(1) as u32);
+    for (index, frame) in frames.iter().enumerate() {
+        imageops::replace(
+            &mut sprite_sheet,
+            frame.buffer(),
+            0,
+            (height * index as u32) as i64,
+        );
+    }
+    Ok(DynamicImage::ImageRgba8(sprite_sheet))
+}
+
+// the trail of the entity, or the trail of the world when the entity has none
+fn entity_trail(world_trail: &Trail, entity: &Entity) -> Trail {
+    if entity.trail.is_default() {
+        *world_trail
+    } else {
+        entity.trail
+    }
+}
+
+// the color of the entity at a pixel, none outside the entity or where it is see-through
+fn entity_pixel_rgba_hex<'a>(
+    entity: &'a Entity,
+    shape: &EntityShape,
+    pixel_x_position: i32,
+    pixel_y_position: i32,
+    images: &HashMap<String, DynamicImage>,
+) -> Option<Cow<'a, str>> {
+    if !entity.is_within_entity(shape, pixel_x_position, pixel_y_position) {
+        return None;
+    }
+
+    // the pixel within the entity, turned back with the entity
+    let (x_offset, y_offset) = entity.local_offset(shape, pixel_x_position, pixel_y_position);
+    let (x_offset, y_offset) = (x_offset.round() as i32, y_offset.round() as i32);
+    let image_rgba_hex = match &entity.entity_type {
+        // pixels outside the image are see-through
+        EntityType::Image(image_path, _, _) => {
+            get_hex_pixel_color_from_image(x_offset, y_offset, image_path, images)?
+        }
+        EntityType::Sprite { path, .. } => {
+            let sprite_sheet = images.get(path)?;
+            let (frame_x_position, frame_y_position) =
+                entity.sprite_frame_position(sprite_sheet.width(), sprite_sheet.height());
+            // frames cut off at the edge of the sprite sheet are see-through
+            get_hex_pixel_color_from_image(
+                x_offset + frame_x_position,
+                y_offset + frame_y_position,
+                path,
+                images,
+            )?
+        }
+        // shapes without a style are filled with the entity color
+        _ if entity.style.is_default() => return Some(Cow::Borrowed(&entity.rgba_hex)),
+        // none where the entity is see-through, like the inside of a hollow entity
+        _ => {
+            return entity
+                .styled_rgba_hex(shape, pixel_x_position, pixel_y_position)
+                .map(Cow::Owned)
+        }
+    };
+
+    if &image_rgba_hex[6..] == "00" {
+        None
+    } else {
+        Some(Cow::Owned(entity.tinted_rgba_hex(image_rgba_hex)))
+    }
+}
+
+// check the entity shape and the random fields of the group before creating entities from it
+fn check_entity_group(entity_group: &EntityGroup) -> Result<(), SpawnError> {
+    if !entity_group.entity_type.is_valid_shape() {
+        return Err(SpawnError::InvalidEntitySize {
+            entity_type: entity_group.entity_type.clone(),
+        });
+    }
+    if let Some(field_name) = entity_group.invalid_random_field() {
+        return Err(SpawnError::InvalidChoices { field_name });
+    }
+    Ok(())
+}
+
+impl Boundary {
+    pub fn is_default(&self) -> bool {
+        *self == Boundary::default()
+    }
 }
 
 impl World {
-    pub fn new(config: &Config) -> World {
+    pub fn new(config: &Config) -> Result<World, SpawnError> {
+        if config.world_width <= 0 || config.world_height <= 0 {
+            return Err(SpawnError::InvalidWorldSize {
+                width: config.world_width,
+                height: config.world_height,
+            });
+        }
+
         let mut world = World {
             width: config.world_width,
             height: config.world_height,
             world_type: config.world_type.clone(),
             background_rgba_hex: config.world_color.to_string(),
+            trail: config.world_trail,
+            boundary: config.world_boundary,
+            x_boundary: config.world_x_boundary,
+            y_boundary: config.world_y_boundary,
+            emitters: config.world_emitters.clone(),
+            entities: vec![],
+            frames: 0,
+            paint: vec![],
+            despawned: DespawnedStats::default(),
+        };
+
+        // emitted entities are checked before the world runs, the largest ones must fit the world
+        // unless they can be fitted, see World::emit()
+        world.check_emitters()?;
+        for emitter in &world.emitters {
+            if let Some(size) = emitter.entities.size.largest() {
+                spawn::fit_entity_type(
+                    &emitter.entities.entity_type.scaled(size),
+                    &emitter.entities.spawn,
+                    config.entity_fit,
+                    world.width,
+                    world.height,
+                )?;
+            }
+        }
+
+        for entity_group in config.all_entity_groups() {
+            check_entity_group(&entity_group)?;
+
+            for index in 0..entity_group.count {
+                let mut entity = Entity::new(&entity_group);
+                entity.entity_type = spawn::fit_entity_type(
+                    &entity.entity_type,
+                    &entity_group.spawn,
+                    config.entity_fit,
+                    world.width,
+                    world.height,
+                )?;
+                world.place_entity(&mut entity, &entity_group, index);
+                world.entities.push(entity);
+            }
+        }
+
+        Ok(world)
+    }
+
+    // check the emitted entities and the areas they are emitted from, also for worlds loaded from world saves
+    pub fn check_emitters(&self) -> Result<(), SpawnError> {
+        for emitter in &self.emitters {
+            check_entity_group(&emitter.entities)?;
+            emitter.entities.spawn.area_size(self.width, self.height)?;
+        }
+        Ok(())
+    }
+
+    // move the entity to its spawn position, random positions are picked again while they overlap
+    fn place_entity(&self, entity: &mut Entity, entity_group: &EntityGroup, index: i32) {
+        let spawn = &entity_group.spawn;
+        let attempts = if entity_group.no_overlap && spawn.is_random() {
+            SPAWN_ATTEMPTS
+        } else {
+            1
+        };
+
+        for _ in 0..attempts {
+            (entity.x_position, entity.y_position) =
+                spawn.position(entity, index, entity_group.count, self.width, self.height);
+            if !self.overlaps_entity(entity) {
+                return;
+            }
+        }
+
+        if entity_group.no_overlap {
+            eprintln!(
+                "warning: could not place entity {} ({}) without overlapping other entities",
+                self.entities.len(),
+                entity.entity_type.name()
+            );
+        }
+    }
+
+    fn overlaps_entity(&self, entity: &Entity) -> bool {
+        let (left, top, right, bottom) = entity.bounds();
+        self.entities.iter().any(|other_entity| {
+            let (other_left, other_top, other_right, other_bottom) = other_entity.bounds();
+            left < other_right && other_left < right && top < other_bottom && other_top < bottom
+        })
+    }
+
+    // combine the entities of several worlds into one world
+    pub fn merge(worlds: Vec<World>, options: &MergeOptions) -> Result<World, String> {
+        if options.background_from >= worlds.len() {
+            return Err(format!(
+                "can not take the background from world {}, there are {} worlds",
+                options.background_from,
+                worlds.len()
+            ));
+        }
+
+        let background_world = &worlds[options.background_from];
+        let mut merged_world = World {
+            width: background_world.width,
+            height: background_world.height,
+            world_type: background_world.world_type.clone(),
+            background_rgba_hex: background_world.background_rgba_hex.to_string(),
+            trail: background_world.trail,
+            boundary: background_world.boundary,
+            x_boundary: background_world.x_boundary,
+            y_boundary: background_world.y_boundary,
+            emitters: vec![],
             entities: vec![],
+            frames: 0,
+            paint: vec![],
+            despawned: DespawnedStats::default(),
         };
+        if options.size_mismatch == SizeMismatch::Grow {
+            merged_world.width = worlds.iter().map(|world| world.width).max().unwrap_or(0);
+            merged_world.height = worlds.iter().map(|world| world.height).max().unwrap_or(0);
+        }
 
-        for _ in (0..config.entity_count).into_iter() {
-            world.entities.push(Entity::new(&config));
+        for (world_index, world) in worlds.into_iter().enumerate() {
+            let is_background_world = world_index == options.background_from;
+            let (merged_width, merged_height) = (merged_world.width, merged_world.height);
+            // positions of the other worlds are scaled to the merged world and moved by the offset
+            let move_position = |(x_position, y_position): (i32, i32)| {
+                if is_background_world {
+                    return (x_position, y_position);
+                }
+                let (x_position, y_position) = if options.size_mismatch == SizeMismatch::Scale
+                    && world.width > 0
+                    && world.height > 0
+                {
+                    (
+                        x_position * merged_width / world.width,
+                        y_position * merged_height / world.height,
+                    )
+                } else {
+                    (x_position, y_position)
+                };
+                (x_position + options.offset.0, y_position + options.offset.1)
+            };
+
+            for mut entity in world.entities {
+                (entity.x_position, entity.y_position) =
+                    move_position((entity.x_position, entity.y_position));
+                if options.size_mismatch == SizeMismatch::Clamp {
+                    merged_world.clamp_entity(&mut entity);
+                }
+                merged_world.entities.push(entity);
+            }
+            // the emitters of every world are kept, emitting from the same place as the entities moved to
+            for mut emitter in world.emitters {
+                emitter.entities.spawn = emitter.entities.spawn.moved(move_position);
+                merged_world.emitters.push(emitter);
+            }
         }
 
-        world
+        Ok(merged_world)
     }
 
-    // update world every frame
-    pub fn update(&mut self, config: &Config) {
+    // move the entity back inside the world, entities larger than the world are aligned to the top left
+    fn clamp_entity(&self, entity: &mut Entity) {
+        let (_, _, right, bottom) = entity.bounds();
+        entity.x_position -= (right - self.width).max(0);
+        entity.y_position -= (bottom - self.height).max(0);
+
+        let (left, top, _, _) = entity.bounds();
+        entity.x_position -= left.min(0);
+        entity.y_position -= top.min(0);
+    }
+
+    // update world every frame, delta_time is the time since the last update
+    pub fn update(&mut self, config: &Config, delta_time: Duration) {
+        self.frames += 1;
+
         // loop through all entities and update each
+        let (x_boundary, y_boundary) = self.boundaries();
+        let obstacles: Vec<_> = self
+            .entities
+            .iter()
+            .filter(|entity| entity.is_static)
+            .map(Entity::bounds)
+            .collect();
         for entity in &mut self.entities {
-            entity.update(&config);
+            if let Trail::Fade(length) = entity_trail(&self.trail, entity) {
+                entity.record_trail_position(length);
+            }
+            let previous_bounds = entity.bounds();
+            entity.update(
+                (self.width, self.height),
+                x_boundary,
+                y_boundary,
+                delta_time,
+            );
+            if !entity.is_static {
+                entity.bounce_off_obstacles(previous_bounds, &obstacles);
+            }
         }
+
+        // entities that left the world through a despawning edge or lived past their lifetime are gone
+        let (width, height) = (self.width, self.height);
+        let despawned = &mut self.despawned;
+        self.entities.retain(|entity| {
+            let (left, top, right, bottom) = entity.bounds();
+            let is_x_outside = right <= 0 || left >= width;
+            let is_y_outside = bottom <= 0 || top >= height;
+            let is_outside = is_x_outside && x_boundary == Boundary::Despawn
+                || is_y_outside && y_boundary == Boundary::Despawn;
+            let is_alive = !entity.is_expired() && (entity.is_static || !is_outside);
+            if !is_alive {
+                despawned.record(&entity.stats);
+            }
+            is_alive
+        });
+
+        self.emit(config, delta_time);
+    }
+
+    // spawn the entities the emitters are due to emit, after the other entities moved
+    fn emit(&mut self, config: &Config, delta_time: Duration) {
+        for emitter_index in 0..self.emitters.len() {
+            let population = self
+                .entities
+                .iter()
+                .filter(|entity| entity.emitter == Some(emitter_index))
+                .count() as i32;
+            let count = self.emitters[emitter_index].due(delta_time, population);
+
+            for _ in 0..count {
+                let emitter = &self.emitters[emitter_index];
+                let mut entity = Entity::new(&emitter.entities);
+                // entities are fitted once their size is picked, entities that can not be fitted are not emitted
+                let Ok(entity_type) = spawn::fit_entity_type(
+                    &entity.entity_type,
+                    &emitter.entities.spawn,
+                    config.entity_fit,
+                    self.width,
+                    self.height,
+                ) else {
+                    continue;
+                };
+                entity.entity_type = entity_type;
+                entity.emitter = Some(emitter_index);
+                // the index only spreads the entities over Positions, it starts over after i32::MAX entities
+                let index = (emitter.emitted % i32::MAX as u64) as i32;
+                self.place_entity(&mut entity, &emitter.entities, index);
+                self.emitters[emitter_index].emitted += 1;
+                self.entities.push(entity);
+            }
+        }
+    }
+
+    // the boundary of the left and right edges and of the top and bottom edges
+    pub fn boundaries(&self) -> (Boundary, Boundary) {
+        (
+            self.x_boundary.unwrap_or(self.boundary),
+            self.y_boundary.unwrap_or(self.boundary),
+        )
+    }
+
+    // the offsets to draw entities at, entities crossing a wrapping edge are also drawn at the opposite edge
+    fn wrap_offsets(&self) -> Vec<(i32, i32)> {
+        let (x_boundary, y_boundary) = self.boundaries();
+        let x_offsets: &[i32] = if x_boundary == Boundary::Wrap {
+            &[0, -self.width, self.width]
+        } else {
+            &[0]
+        };
+        let y_offsets: &[i32] = if y_boundary == Boundary::Wrap {
+            &[0, -self.height, self.height]
+        } else {
+            &[0]
+        };
+        y_offsets
+            .iter()
+            .flat_map(|y_offset| x_offsets.iter().map(|x_offset| (*x_offset, *y_offset)))
+            .collect()
+    }
+
+    // paint the entities with a Paint trail onto the world background, every frame after updating
+    pub fn paint(&mut self, images: &HashMap<String, DynamicImage>) {
+        let wrap_offsets = self.wrap_offsets();
+        for entity in &self.entities {
+            if entity_trail(&self.trail, entity) != Trail::Paint {
+                continue;
+            }
+            if self.paint.is_empty() {
+                self.paint = vec![None; self.width.max(0) as usize * self.height.max(0) as usize];
+            }
+
+            let shape = entity.shape();
+            let (left, top, right, bottom) = shape.bounds;
+            for (x_offset, y_offset) in &wrap_offsets {
+                let (left, right) = (left - x_offset, right - x_offset);
+                let (top, bottom) = (top - y_offset, bottom - y_offset);
+                for y_position in top.max(0)..bottom.min(self.height) {
+                    for x_position in left.max(0)..right.min(self.width) {
+                        if let Some(rgba_hex) = entity_pixel_rgba_hex(
+                            entity,
+                            &shape,
+                            x_position + x_offset,
+                            y_position + y_offset,
+                            images,
+                        ) {
+                            self.paint[(y_position * self.width + x_position) as usize] =
+                                Some(rgba_hex.into_owned());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // the bounces, corner hits and distance of every entity since the world started running
+    pub fn stats(&self) -> WorldStats {
+        WorldStats {
+            frames: self.frames,
+            bounces: self.despawned.bounces
+                + self
+                    .entities
+                    .iter()
+                    .map(|entity| entity.stats.bounces)
+                    .sum::<u64>(),
+            corner_hits: self.despawned.corner_hits
+                + self
+                    .entities
+                    .iter()
+                    .map(|entity| entity.stats.corner_hits)
+                    .sum::<u64>(),
+            despawned: self.despawned.entities,
+            entities: self
+                .entities
+                .iter()
+                .enumerate()
+                .map(|(index, entity)| EntityStatsSummary {
+                    index,
+                    id: entity.id.clone(),
+                    entity_type: entity.entity_type.name().to_string(),
+                    stats: entity.stats.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    // the entities that hit a corner in the last update, by index
+    pub fn corner_hits_this_frame(&self) -> Vec<(usize, Corner)> {
+        self.entities
+            .iter()
+            .enumerate()
+            .filter_map(|(index, entity)| {
+                entity
+                    .stats
+                    .corner_hit_this_frame()
+                    .map(|corner| (index, corner))
+            })
+            .collect()
+    }
+
+    // load the images used by the world and its entities into memory
+    pub fn load_images(&self) -> Result<HashMap<String, DynamicImage>, String> {
+        let world_image_path = match &self.world_type {
+            WorldType::Image(image_path, _, _) => Some(image_path),
+            WorldType::Color => None,
+        };
+        // emitted entities are loaded before they are emitted
+        let entity_types = self
+            .entities
+            .iter()
+            .map(|entity| &entity.entity_type)
+            .chain(
+                self.emitters
+                    .iter()
+                    .map(|emitter| &emitter.entities.entity_type),
+            );
+        let entity_image_paths = entity_types.filter_map(|entity_type| match entity_type {
+            EntityType::Image(image_path, _, _) => Some(image_path),
+            EntityType::Sprite { path, .. } => Some(path),
+            _ => None, // not an image
+        });
+
+        let mut images = HashMap::new();
+        for image_path in world_image_path.into_iter().chain(entity_image_paths) {
+            if !images.contains_key(image_path) {
+                let image = open_image(image_path)
+                    .map_err(|error| format!("could not read image {:?}: {}", image_path, error))?;
+                images.insert(image_path.to_string(), image);
+            }
+        }
+        Ok(images)
     }
 
     // draw world every frame, the world handles the color of each pixel
-    pub fn draw(
-        &mut self,
-        frame: &mut [u8],
-        config: &Config,
-        images: &HashMap<String, DynamicImage>,
-    ) {
-        let mut image_entity_starting_positions: HashMap<usize, (i32, i32)> = HashMap::new();
+    pub fn draw(&self, frame: &mut [u8], images: &HashMap<String, DynamicImage>) {
+        let wrap_offsets = self.wrap_offsets();
+        let shapes: Vec<EntityShape> = self.entities.iter().map(Entity::shape).collect();
 
         // loop through each pixel (frame split in four due to rrggbbaa format)
         for (i, pixel) in frame.chunks_exact_mut(4).enumerate() {
             // calculate pixels x and y positions on frame
-            let pixel_x_position = (i % config.world_width as usize) as i32;
-            let pixel_y_position = (i / config.world_width as usize) as i32;
+            let pixel_x_position = (i % self.width as usize) as i32;
+            let pixel_y_position = (i / self.width as usize) as i32;
 
-            // TODO: remove?
-            let mut temp_entity_rgba_hex_str;
+            let mut rgba_hex_str: Cow<str> = match &self.world_type {
+                _ if self.paint.get(i).is_some_and(Option::is_some) => {
+                    Cow::Borrowed(self.paint[i].as_deref().expect("painted pixel has a color"))
+                }
+                WorldType::Color => Cow::Borrowed(&self.background_rgba_hex),
+                WorldType::Image(image_path, width, height)
+                    if pixel_x_position < *width && pixel_y_position < *height =>
+                {
+                    get_hex_pixel_color_from_image(
+                        pixel_x_position,
+                        pixel_y_position,
+                        image_path,
+                        images,
+                    )
+                    .map_or(Cow::Borrowed(&self.background_rgba_hex), Cow::Owned)
+                }
+                WorldType::Image(_, _, _) => Cow::Borrowed(&self.background_rgba_hex),
+            };
 
-            let mut is_pixel_occupied = false;
-            let mut rgba_hex_str = match &self.world_type {
-                WorldType::Color => &self.background_rgba_hex,
-                WorldType::Image(image_path, width, height) => {
-                    if pixel_x_position <= *width && pixel_y_position <= *height {
-                        temp_entity_rgba_hex_str = get_hex_pixel_color_from_image(
-                            pixel_x_position,
-                            pixel_y_position,
-                            image_path,
+            // fading trails are drawn below all entities, older positions fade more
+            for (entity, shape) in self.entities.iter().zip(&shapes) {
+                let trail_length = entity.trail_positions.len();
+                for (index, (x_position, y_position)) in entity.trail_positions.iter().enumerate() {
+                    let trail_rgba_hex = wrap_offsets.iter().find_map(|(x_offset, y_offset)| {
+                        entity_pixel_rgba_hex(
+                            entity,
+                            shape,
+                            pixel_x_position + x_offset - x_position + entity.x_position,
+                            pixel_y_position + y_offset - y_position + entity.y_position,
                             images,
-                        );
-
-                        &temp_entity_rgba_hex_str
-                    } else {
-                        &self.background_rgba_hex
+                        )
+                    });
+                    if let Some(trail_rgba_hex) = trail_rgba_hex {
+                        let amount = (index + 1) as f64 / (trail_length + 1) as f64;
+                        rgba_hex_str =
+                            Cow::Owned(style::mix_rgba_hex(&rgba_hex_str, &trail_rgba_hex, amount));
                     }
                 }
-            };
-            for (entity_index, entity) in self.entities.iter().enumerate() {
-                if entity.is_within_entity(pixel_x_position, pixel_y_position) {
-                    rgba_hex_str = match &entity.entity_type {
-                        EntityType::Box(_, _) => &entity.rgba_hex,
-                        EntityType::Ball(_) => &entity.rgba_hex,
-                        EntityType::Image(image_path, _, _) => {
-                            if !image_entity_starting_positions.contains_key(&entity_index) {
-                                image_entity_starting_positions
-                                    .insert(entity_index, (pixel_x_position, pixel_y_position));
-                            }
-
-                            if !is_pixel_occupied {
-                                is_pixel_occupied = true;
-                                let (starting_image_x_position, starting_image_y_position) =
-                                image_entity_starting_positions.get(&entity_index).expect(
-                                    "failed to get image_entity_starting_positions by entity_index",
-                                );
-
-                                let entity_hex_color = get_hex_pixel_color_from_image(
-                                    pixel_x_position - starting_image_x_position,
-                                    pixel_y_position - starting_image_y_position,
-                                    image_path,
-                                    images,
-                                );
-
-                                if &entity_hex_color[6..] == "00" {
-                                    is_pixel_occupied = false;
-                                    &rgba_hex_str
-                                } else {
-                                    temp_entity_rgba_hex_str = entity_hex_color;
-                                    &temp_entity_rgba_hex_str
-                                }
-                            } else {
-                                &rgba_hex_str
-                            }
-                        }
-                        EntityType::Text(_, _) => &entity.rgba_hex,
-                    };
+            }
+
+            // images are drawn below the images before them, other entities are drawn on top
+            let mut is_pixel_occupied = false;
+            for (entity, shape) in self.entities.iter().zip(&shapes) {
+                let is_image = matches!(entity.entity_type, EntityType::Image(_, _, _));
+                if is_image && is_pixel_occupied {
+                    continue;
+                }
+                let entity_rgba_hex = wrap_offsets.iter().find_map(|(x_offset, y_offset)| {
+                    entity_pixel_rgba_hex(
+                        entity,
+                        shape,
+                        pixel_x_position + x_offset,
+                        pixel_y_position + y_offset,
+                        images,
+                    )
+                });
+                if let Some(entity_rgba_hex) = entity_rgba_hex {
+                    is_pixel_occupied |= is_image;
+                    rgba_hex_str = entity_rgba_hex;
                 }
             }
 
             pixel.copy_from_slice(
-                &<[u8; 4]>::from_hex(rgba_hex_str).expect(
+                &<[u8; 4]>::from_hex(rgba_hex_str.as_bytes()).expect(
                     "issue converting rgba_str to rgba u8 slice, expected format: rrggbbaa",
                 ),
             );
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    use crate::{
+        config::EntityGroup,
+        random::{RandomChoice, RandomNumber},
+        save::Save,
+        spawn::{EntityFit, Spawn},
+    };
+
+    fn config_with(world_width: i32, world_height: i32, entity_type: EntityType) -> Config {
+        let mut config = Config::new();
+        config.world_width = world_width;
+        config.world_height = world_height;
+        config.entity_type = entity_type;
+        config
+    }
+
+    #[test]
+    fn new_rejects_zero_and_negative_world_sizes() {
+        for (width, height) in [(0, 100), (100, 0), (-10, 100), (100, -1), (-5, -5)] {
+            let config = config_with(width, height, EntityType::Box(10, 10));
+            assert_eq!(
+                World::new(&config),
+                Err(SpawnError::InvalidWorldSize { width, height })
+            );
+        }
+    }
+
+    #[test]
+    fn new_rejects_invalid_entity_sizes() {
+        for entity_type in [
+            EntityType::Box(-5, 10),
+            EntityType::Ball(0),
+            EntityType::RegularPolygon(2, 10),
+        ] {
+            let config = config_with(100, 100, entity_type.clone());
+            assert_eq!(
+                World::new(&config),
+                Err(SpawnError::InvalidEntitySize { entity_type })
+            );
+        }
+    }
+
+    #[test]
+    fn new_rejects_oversize_entities_by_default() {
+        let mut config = config_with(100, 100, EntityType::Box(150, 50));
+        config.entity_fit = EntityFit::Error;
+        assert!(matches!(
+            World::new(&config),
+            Err(SpawnError::EntityTooLarge { .. })
+        ));
+    }
+
+    #[test]
+    fn new_fits_oversize_entities_when_allowed() {
+        for entity_type in [EntityType::Box(150, 50), EntityType::Ball(80)] {
+            // entities larger than the world are shrunk whether they are clamped or shrunk,
+            // a clamped entity wider than the world would bounce off both edges every frame
+            for entity_fit in [EntityFit::Clamp, EntityFit::Shrink] {
+                let mut config = config_with(100, 100, entity_type.clone());
+                config.entity_fit = entity_fit;
+                let world = World::new(&config).expect("oversize entities fit the world");
+                let (left, top, right, bottom) = world.entities[0].bounds();
+                assert_ne!(world.entities[0].entity_type, entity_type);
+                assert!(left >= 0 && top >= 0 && right <= 100 && bottom <= 100);
+            }
+        }
+
+        // clamped entities that fit the world but not their spawn region keep their size
+        let mut config = config_with(100, 100, EntityType::Box(40, 40));
+        config.entity_spawn = Spawn::Region {
+            x: 10,
+            y: 10,
+            width: 20,
+            height: 20,
+        };
+        config.entity_fit = EntityFit::Clamp;
+        let world = World::new(&config).expect("clamped entities fit the world");
+        assert_eq!(world.entities[0].entity_type, EntityType::Box(40, 40));
+        assert_eq!(world.entities[0].bounds(), (10, 10, 50, 50));
+    }
+
+    #[test]
+    fn emitted_entities_fit_the_world() {
+        // boxes of 40x40 up to 160x160 pixels
+        let mut config = config_with(100, 100, EntityType::Box(10, 10));
+        config.world_emitters = vec![Emitter {
+            rate: 50.0,
+            entities: EntityGroup {
+                entity_type: EntityType::Box(40, 40),
+                size: RandomNumber::Range { min: 1.0, max: 4.0 },
+                ..EntityGroup::new()
+            },
+            ..Emitter::new()
+        }];
+        config.entity_fit = EntityFit::Error;
+        assert!(matches!(
+            World::new(&config),
+            Err(SpawnError::EntityTooLarge { .. })
+        ));
+
+        config.entity_fit = EntityFit::Shrink;
+        let mut world = World::new(&config).expect("shrunk emitted entities fit the world");
+        world.entities.clear();
+        world.update(&config, Duration::from_secs(1));
+        assert_eq!(world.entities.len(), 50);
+        for entity in &world.entities {
+            let (left, top, right, bottom) = entity.bounds();
+            assert!(left >= 0 && top >= 0 && right <= 100 && bottom <= 100);
+        }
+    }
+
+    #[test]
+    fn loading_a_world_save_checks_its_emitters() {
+        let config = config_with(100, 100, EntityType::Box(10, 10));
+        let mut world = World::new(&config).expect("boxes fit the world");
+        world.emitters = vec![Emitter {
+            entities: EntityGroup {
+                color: RandomChoice::Choices(vec![]),
+                ..EntityGroup::new()
+            },
+            ..Emitter::new()
+        }];
+        let save_path = std::env::temp_dir().join(format!(
+            "bouncy-world-test-{}.bouncy-world",
+            std::process::id()
+        ));
+        fs::write(
+            &save_path,
+            serde_yaml::to_string(&Save::new(world)).expect("world saves serialize"),
+        )
+        .expect("could not write the world save");
+
+        let result = Save::try_load(&save_path);
+        fs::remove_file(&save_path).expect("could not remove the world save");
+        assert!(result.is_err_and(|error| error.contains("color")));
+    }
+
+    #[test]
+    fn stats_keep_despawned_entities() {
+        let config = config_with(100, 100, EntityType::Box(10, 10));
+        let mut world = World::new(&config).expect("boxes fit the world");
+        world.x_boundary = Some(Boundary::Despawn);
+        world.entities.truncate(1);
+        // bounces off the bottom edge, then leaves through the left edge
+        (world.entities[0].x_position, world.entities[0].y_position) = (50, 85);
+        (world.entities[0].x_velocity, world.entities[0].y_velocity) = (-10, 10);
+
+        let mut bounces = 0;
+        while let Some(entity) = world.entities.first() {
+            bounces = entity.stats.bounces;
+            world.update(&config, Duration::ZERO);
+        }
+        let stats = world.stats();
+        assert!(bounces > 0);
+        assert_eq!(stats.bounces, bounces);
+        assert_eq!(stats.despawned, 1);
+    }
+
+    #[test]
+    fn merge_keeps_the_emitters_of_every_world() {
+        let mut config = config_with(100, 100, EntityType::Box(10, 10));
+        config.world_emitters = vec![Emitter {
+            entities: EntityGroup {
+                spawn: Spawn::Positions(vec![(10, 10)]),
+                ..EntityGroup::new()
+            },
+            ..Emitter::new()
+        }];
+        let worlds = vec![
+            World::new(&config).expect("boxes fit the world"),
+            World::new(&config).expect("boxes fit the world"),
+        ];
+        let options = MergeOptions {
+            background_from: 0,
+            offset: (5, -5),
+            size_mismatch: SizeMismatch::Keep,
+        };
+
+        let merged_world = World::merge(worlds, &options).expect("the background world exists");
+        let spawns: Vec<&Spawn> = merged_world
+            .emitters
+            .iter()
+            .map(|emitter| &emitter.entities.spawn)
+            .collect();
+        assert_eq!(
+            spawns,
+            [
+                &Spawn::Positions(vec![(10, 10)]),
+                &Spawn::Positions(vec![(15, 5)])
+            ]
+        );
+    }
+
+    #[test]
+    fn draw_images_smaller_than_their_size() {
+        let mut config = config_with(20, 20, EntityType::Image("small.png".to_string(), 10, 10));
+        config.world_type = WorldType::Image("small.png".to_string(), 20, 20);
+        let world = World::new(&config).expect("images fit the world");
+        let images = HashMap::from([("small.png".to_string(), DynamicImage::new_rgba8(2, 2))]);
+
+        let mut frame = vec![0; 20 * 20 * 4];
+        world.draw(&mut frame, &images);
+    }
+}